@@ -1,116 +1,23 @@
 #![forbid(unsafe_code)]
-use std::{path::PathBuf, time::Duration};
+use std::time::Duration;
 
 mod data;
+mod scenario_builder;
 use tokio::{runtime::Runtime, time::sleep};
 use zingo_cli::regtest::{ChildProcessHandler, RegtestManager};
-use zingoconfig::ZingoConfig;
-use zingolib::{create_zingoconf_with_datadir, lightclient::LightClient};
+use zingolib::lightclient::LightClient;
 
-///  Test setup involves common configurations files.  Contents and locations
-///  are variable.
-///   Locations:
-///     Each test must have a unique set of config files.  By default those
-///     files will be preserved on test failure.
-///   Contents:
-///     The specific configuration values may or may not differ between
-///     scenarios and/or tests.
-///     Data templates for config files are in:
-///        * tests::data::config_template_fillers::zcashd
-///        * tests::data::config_template_fillers::lightwalletd
-struct TestConfigGenerator {
-    zcash_conf_location: PathBuf,
-    lightwalletd_conf_location: PathBuf,
-    zcashd_chain_port: u16,
-}
-impl TestConfigGenerator {
-    fn new(zcash_pathbase: &str, lightwalletd_pathbase: &str) -> Self {
-        let mut common_path = zingo_cli::regtest::get_git_rootdir();
-        common_path.push("cli");
-        common_path.push("tests");
-        common_path.push("data");
-        let zcash_conf_location = common_path.join(zcash_pathbase);
-        let lightwalletd_conf_location = common_path.join(lightwalletd_pathbase);
-        let zcashd_chain_port = portpicker::pick_unused_port().expect("Port unpickable!");
-        Self {
-            zcash_conf_location,
-            lightwalletd_conf_location,
-            zcashd_chain_port,
-        }
-    }
+use scenario_builder::ScenarioBuilder;
 
-    fn create_unfunded_zcash_conf(&self) -> PathBuf {
-        self.write_contents_and_return_path(
-            "zcash",
-            data::config_template_fillers::zcashd::basic(
-                dbg!(format!("{:?}", self.zcashd_chain_port).as_str()),
-                "",
-            ),
-        )
-    }
-    fn create_funded_zcash_conf(&self, address_to_fund: &str) -> PathBuf {
-        self.write_contents_and_return_path(
-            "zcash",
-            data::config_template_fillers::zcashd::funded(
-                address_to_fund,
-                dbg!(format!("{:?}", self.zcashd_chain_port).as_str()),
-            ),
-        )
-    }
-    fn create_lightwalletd_conf(&self) -> PathBuf {
-        self.write_contents_and_return_path(
-            "lightwalletd",
-            data::config_template_fillers::lightwalletd::basic(),
-        )
-    }
-    fn write_contents_and_return_path(&self, configtype: &str, contents: String) -> PathBuf {
-        let loc = match configtype {
-            "zcash" => &self.zcash_conf_location,
-            "lightwalletd" => &self.lightwalletd_conf_location,
-            _ => panic!("Unepexted configtype!"),
-        };
-        let mut output = std::fs::File::create(&loc).expect("How could path {config} be missing?");
-        std::io::Write::write(&mut output, contents.as_bytes())
-            .expect("Couldn't write {contents}!");
-        loc.clone()
-    }
-}
-fn create_maybe_funded_regtest_manager(
-    zcash_pathbase: &str,
-    lightwalletd_pathbase: &str,
-    fund_recipient_address: Option<&str>,
-) -> RegtestManager {
-    let test_configs = TestConfigGenerator::new(zcash_pathbase, lightwalletd_pathbase);
-    RegtestManager::new(
-        Some(match fund_recipient_address {
-            Some(fund_to_address) => test_configs.create_funded_zcash_conf(fund_to_address),
-            None => test_configs.create_unfunded_zcash_conf(),
-        }),
-        Some(test_configs.create_lightwalletd_conf()),
-    )
-}
 /// The general scenario framework requires instances of zingo-cli, lightwalletd, and zcashd (in regtest mode).
 /// This setup is intended to produce the most basic of scenarios.  As scenarios with even less requirements
 /// become interesting (e.g. without experimental features, or txindices) we'll create more setups.
 fn basic_funded_zcashd_lwd_zingolib_connected_setup(
 ) -> (RegtestManager, ChildProcessHandler, LightClient) {
-    let regtest_manager = create_maybe_funded_regtest_manager(
-        "basic_zcashd.conf",
-        "lightwalletd.yml",
-        Some(data::SAPLING_ADDRESS_FROM_SPEND_AUTH),
-    );
-    let child_process_handler = regtest_manager.launch(true).unwrap();
-    let server_id = ZingoConfig::get_server_or_default(Some("http://127.0.0.1".to_string()));
-    let (config, _height) = create_zingoconf_with_datadir(
-        server_id,
-        Some(regtest_manager.zingo_datadir.to_string_lossy().to_string()),
-    )
-    .unwrap();
-    (
-        regtest_manager,
-        child_process_handler,
-        LightClient::new(&config, 0).unwrap(),
-    )
+    ScenarioBuilder::new()
+        .with_zcashd_conf("basic_zcashd.conf")
+        .funded_to(data::SAPLING_ADDRESS_FROM_SPEND_AUTH)
+        .build()
 }
 #[ignore]
 #[test]
@@ -127,45 +34,21 @@ fn coinbasebacked_spendcapable_setup() -> (RegtestManager, ChildProcessHandler,
 {
     //tracing_subscriber::fmt::init();
     let coinbase_spendkey = include_str!("data/mineraddress_sapling_spendingkey").to_string();
-    let regtest_manager = create_maybe_funded_regtest_manager(
-        "externalwallet_coinbaseaddress.conf",
-        "lightwalletd.yml",
-        Some(data::SAPLING_ADDRESS_FROM_SPEND_AUTH),
-    );
-    let child_process_handler = regtest_manager.launch(true).unwrap();
-    let server_id = ZingoConfig::get_server_or_default(Some("http://127.0.0.1".to_string()));
-    let (config, _height) = create_zingoconf_with_datadir(
-        server_id,
-        Some(regtest_manager.zingo_datadir.to_string_lossy().to_string()),
-    )
-    .unwrap();
-    regtest_manager.generate_n_blocks(5).unwrap();
+    let (regtest_manager, child_process_handler, client) = ScenarioBuilder::new()
+        .funded_to(data::SAPLING_ADDRESS_FROM_SPEND_AUTH)
+        .spend_capable_from(&coinbase_spendkey)
+        .mine_blocks(5)
+        .build();
     (
         regtest_manager,
         child_process_handler,
-        LightClient::create_with_capable_wallet(coinbase_spendkey, &config, 0, false).unwrap(),
+        client,
         Runtime::new().unwrap(),
     )
 }
 
 fn basic_no_spendable_setup() -> (RegtestManager, ChildProcessHandler, LightClient) {
-    let regtest_manager = create_maybe_funded_regtest_manager(
-        "externalwallet_coinbaseaddress.conf",
-        "lightwalletd.yml",
-        None,
-    );
-    let child_process_handler = regtest_manager.launch(true).unwrap();
-    let server_id = ZingoConfig::get_server_or_default(Some("http://127.0.0.1".to_string()));
-    let (config, _height) = create_zingoconf_with_datadir(
-        server_id,
-        Some(regtest_manager.zingo_datadir.to_string_lossy().to_string()),
-    )
-    .unwrap();
-    (
-        regtest_manager,
-        child_process_handler,
-        LightClient::new(&config, 0).unwrap(),
-    )
+    ScenarioBuilder::new().build()
 }
 
 #[test]
@@ -239,22 +122,60 @@ fn mine_sapling_to_self() {
     assert_eq!(balance["sapling_balance"], 625000000);
 }
 
-#[ignore]
-#[test]
-fn send_mined_sapling_to_orchard() {
+/// A shielded pool a test can send from or to, via its `do_new_address` pool code and the
+/// `{unverified,verified}_{pool}_balance` keys `do_balance` reports it under.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pool {
+    Sapling,
+    Orchard,
+}
+
+impl Pool {
+    fn code(self) -> &'static str {
+        match self {
+            Pool::Sapling => "z",
+            Pool::Orchard => "o",
+        }
+    }
+
+    fn balance_key(self, verified: bool) -> String {
+        let pool = match self {
+            Pool::Sapling => "sapling",
+            Pool::Orchard => "orchard",
+        };
+        format!("{}_{pool}_balance", if verified { "verified" } else { "unverified" })
+    }
+}
+
+/// Drives a send from `source` to a fresh `dest` address and asserts the destination pool's
+/// balance lands unverified then verified, exercising Sapling->Orchard, Orchard->Orchard, and
+/// Sapling->Sapling transfers through the same code path instead of per-pool test bodies.
+///
+/// The wallet is funded via coinbase into Sapling; when `source` isn't Sapling, funds are first
+/// shielded from the coinbase balance into `source` so the send under test draws from there.
+fn send_between_pools(source: Pool, dest: Pool, amount: u64) {
     let (regtest_manager, _child_process_handler, client, runtime) =
         coinbasebacked_spendcapable_setup();
     runtime.block_on(async {
         sleep(Duration::from_secs(2)).await;
-        let sync_status = client.do_sync(true).await.unwrap();
-        println!("{}", json::stringify_pretty(sync_status, 4));
+        client.do_sync(true).await.unwrap();
 
-        let o_addr = client.do_new_address("o").await.unwrap()[0].take();
-        println!("{o_addr}");
+        if source != Pool::Sapling {
+            let source_addr = client.do_new_address(source.code()).await.unwrap()[0].take();
+            client
+                .do_send(vec![(source_addr.to_string().as_str(), amount, None)])
+                .await
+                .unwrap();
+            regtest_manager.generate_n_blocks(4).unwrap();
+            sleep(Duration::from_secs(2)).await;
+            client.do_sync(true).await.unwrap();
+        }
+
+        let dest_addr = client.do_new_address(dest.code()).await.unwrap()[0].take();
         let send_status = client
             .do_send(vec![(
-                o_addr.to_string().as_str(),
-                5000,
+                dest_addr.to_string().as_str(),
+                amount,
                 Some("Scenario test: engage!".to_string()),
             )])
             .await
@@ -269,15 +190,69 @@ fn send_mined_sapling_to_orchard() {
         let transactions = client.do_list_transactions(false).await;
         println!("{}", json::stringify_pretty(balance.clone(), 4));
         println!("{}", json::stringify_pretty(transactions, 4));
-        assert_eq!(balance["unverified_orchard_balance"], 5000);
-        assert_eq!(balance["verified_orchard_balance"], 0);
+        assert_eq!(balance[dest.balance_key(false)], amount);
+        assert_eq!(balance[dest.balance_key(true)], 0);
 
         regtest_manager.generate_n_blocks(4).unwrap();
         sleep(Duration::from_secs(2)).await;
         client.do_sync(true).await.unwrap();
         let balance = client.do_balance().await;
         println!("{}", json::stringify_pretty(balance.clone(), 4));
-        assert_eq!(balance["unverified_orchard_balance"], 0);
-        assert_eq!(balance["verified_orchard_balance"], 5000);
+        assert_eq!(balance[dest.balance_key(false)], 0);
+        assert_eq!(balance[dest.balance_key(true)], amount);
+    });
+}
+
+#[ignore]
+#[test]
+fn send_mined_sapling_to_orchard() {
+    send_between_pools(Pool::Sapling, Pool::Orchard, 5000);
+}
+
+#[ignore]
+#[test]
+fn send_orchard_to_orchard() {
+    send_between_pools(Pool::Orchard, Pool::Orchard, 5000);
+}
+
+#[ignore]
+#[test]
+fn send_sapling_to_sapling() {
+    send_between_pools(Pool::Sapling, Pool::Sapling, 5000);
+}
+
+/// Funds a transparent address, syncs, then shields the transparent balance into the Orchard
+/// pool, exercising the t-address receive path this wallet otherwise can't represent.
+#[ignore]
+#[test]
+fn shield_transparent_to_orchard() {
+    let (regtest_manager, _child_process_handler, client, runtime) =
+        coinbasebacked_spendcapable_setup();
+    runtime.block_on(async {
+        sleep(Duration::from_secs(2)).await;
+        client.do_sync(true).await.unwrap();
+
+        let t_addr = client.do_new_address("t").await.unwrap()[0].take();
+        client
+            .do_send(vec![(t_addr.to_string().as_str(), 50_000, None)])
+            .await
+            .unwrap();
+        regtest_manager.generate_n_blocks(4).unwrap();
+        sleep(Duration::from_secs(2)).await;
+        client.do_sync(true).await.unwrap();
+
+        let balance = client.do_balance().await;
+        assert_eq!(balance["transparent_balance"], 50_000);
+
+        let shield_status = client.do_shield().await.unwrap();
+        println!("Shield status: {shield_status}");
+
+        regtest_manager.generate_n_blocks(4).unwrap();
+        sleep(Duration::from_secs(2)).await;
+        client.do_sync(true).await.unwrap();
+
+        let balance = client.do_balance().await;
+        assert_eq!(balance["transparent_balance"], 0);
+        assert_eq!(balance["verified_orchard_balance"], 50_000);
     });
 }
@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use zingo_cli::regtest::{ChildProcessHandler, RegtestManager};
+use zingoconfig::ZingoConfig;
+use zingolib::{create_zingoconf_with_datadir, lightclient::LightClient};
+
+use crate::data;
+
+///  Test setup involves common configurations files.  Contents and locations
+///  are variable.
+///   Locations:
+///     Each test must have a unique set of config files.  By default those
+///     files will be preserved on test failure.
+///   Contents:
+///     The specific configuration values may or may not differ between
+///     scenarios and/or tests.
+///     Data templates for config files are in:
+///        * tests::data::config_template_fillers::zcashd
+///        * tests::data::config_template_fillers::lightwalletd
+pub(crate) struct TestConfigGenerator {
+    zcash_conf_location: PathBuf,
+    lightwalletd_conf_location: PathBuf,
+    zcashd_chain_port: u16,
+}
+impl TestConfigGenerator {
+    fn new(zcash_pathbase: &str, lightwalletd_pathbase: &str) -> Self {
+        let mut common_path = zingo_cli::regtest::get_git_rootdir();
+        common_path.push("cli");
+        common_path.push("tests");
+        common_path.push("data");
+        let zcash_conf_location = common_path.join(zcash_pathbase);
+        let lightwalletd_conf_location = common_path.join(lightwalletd_pathbase);
+        let zcashd_chain_port = portpicker::pick_unused_port().expect("Port unpickable!");
+        Self {
+            zcash_conf_location,
+            lightwalletd_conf_location,
+            zcashd_chain_port,
+        }
+    }
+
+    fn create_unfunded_zcash_conf(&self) -> PathBuf {
+        self.write_contents_and_return_path(
+            "zcash",
+            data::config_template_fillers::zcashd::basic(
+                dbg!(format!("{:?}", self.zcashd_chain_port).as_str()),
+                "",
+            ),
+        )
+    }
+    fn create_funded_zcash_conf(&self, address_to_fund: &str) -> PathBuf {
+        self.write_contents_and_return_path(
+            "zcash",
+            data::config_template_fillers::zcashd::funded(
+                address_to_fund,
+                dbg!(format!("{:?}", self.zcashd_chain_port).as_str()),
+            ),
+        )
+    }
+    fn create_lightwalletd_conf(&self) -> PathBuf {
+        self.write_contents_and_return_path(
+            "lightwalletd",
+            data::config_template_fillers::lightwalletd::basic(),
+        )
+    }
+    fn write_contents_and_return_path(&self, configtype: &str, contents: String) -> PathBuf {
+        let loc = match configtype {
+            "zcash" => &self.zcash_conf_location,
+            "lightwalletd" => &self.lightwalletd_conf_location,
+            _ => panic!("Unepexted configtype!"),
+        };
+        let mut output = std::fs::File::create(&loc).expect("How could path {config} be missing?");
+        std::io::Write::write(&mut output, contents.as_bytes())
+            .expect("Couldn't write {contents}!");
+        loc.clone()
+    }
+}
+
+pub(crate) fn create_maybe_funded_regtest_manager(
+    zcash_pathbase: &str,
+    lightwalletd_pathbase: &str,
+    fund_recipient_address: Option<&str>,
+) -> RegtestManager {
+    let test_configs = TestConfigGenerator::new(zcash_pathbase, lightwalletd_pathbase);
+    RegtestManager::new(
+        Some(match fund_recipient_address {
+            Some(fund_to_address) => test_configs.create_funded_zcash_conf(fund_to_address),
+            None => test_configs.create_unfunded_zcash_conf(),
+        }),
+        Some(test_configs.create_lightwalletd_conf()),
+    )
+}
+
+/// Builds a `(RegtestManager, ChildProcessHandler, LightClient)` scenario from composable
+/// options, replacing the bespoke `*_setup()` functions that used to be forked per test file.
+/// Each option defaults to the cheapest scenario (unfunded chain, fresh seed, no pre-mined
+/// blocks); opt into the pieces a given test actually needs.
+pub(crate) struct ScenarioBuilder {
+    zcash_conf_pathbase: &'static str,
+    lightwalletd_conf_pathbase: &'static str,
+    funded_to: Option<String>,
+    coinbase_spendkey: Option<String>,
+    blocks_to_mine: u64,
+}
+
+impl ScenarioBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            zcash_conf_pathbase: "externalwallet_coinbaseaddress.conf",
+            lightwalletd_conf_pathbase: "lightwalletd.yml",
+            funded_to: None,
+            coinbase_spendkey: None,
+            blocks_to_mine: 0,
+        }
+    }
+
+    /// Funds `addr` via the zcashd conf's `mineraddress`, so the regtest chain has coinbase
+    /// value sitting at a known address from genesis.
+    pub(crate) fn funded_to(mut self, addr: &str) -> Self {
+        self.funded_to = Some(addr.to_string());
+        self
+    }
+
+    pub(crate) fn with_zcashd_conf(mut self, pathbase: &'static str) -> Self {
+        self.zcash_conf_pathbase = pathbase;
+        self
+    }
+
+    pub(crate) fn with_lightwalletd_conf(mut self, pathbase: &'static str) -> Self {
+        self.lightwalletd_conf_pathbase = pathbase;
+        self
+    }
+
+    /// Seeds the built `LightClient` from `spendkey` instead of a fresh random seed, so it can
+    /// spend the funds `funded_to` registered at `funded_to`'s address.
+    pub(crate) fn spend_capable_from(mut self, spendkey: &str) -> Self {
+        self.coinbase_spendkey = Some(spendkey.to_string());
+        self
+    }
+
+    pub(crate) fn mine_blocks(mut self, n: u64) -> Self {
+        self.blocks_to_mine = n;
+        self
+    }
+
+    pub(crate) fn build(self) -> (RegtestManager, ChildProcessHandler, LightClient) {
+        let regtest_manager = create_maybe_funded_regtest_manager(
+            self.zcash_conf_pathbase,
+            self.lightwalletd_conf_pathbase,
+            self.funded_to.as_deref(),
+        );
+        let child_process_handler = regtest_manager.launch(true).unwrap();
+        let server_id = ZingoConfig::get_server_or_default(Some("http://127.0.0.1".to_string()));
+        let (config, _height) = create_zingoconf_with_datadir(
+            server_id,
+            Some(regtest_manager.zingo_datadir.to_string_lossy().to_string()),
+        )
+        .unwrap();
+
+        if self.blocks_to_mine > 0 {
+            regtest_manager.generate_n_blocks(self.blocks_to_mine).unwrap();
+        }
+
+        let client = match self.coinbase_spendkey {
+            Some(spendkey) => {
+                LightClient::create_with_capable_wallet(spendkey, &config, 0, false).unwrap()
+            }
+            None => LightClient::new(&config, 0).unwrap(),
+        };
+
+        (regtest_manager, child_process_handler, client)
+    }
+}
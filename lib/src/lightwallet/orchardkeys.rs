@@ -1,5 +1,25 @@
-use orchard::keys::{Diversifier, FullViewingKey, IncomingViewingKey, OutgoingViewingKey, Scope, SpendingKey};
-use zcash_address::unified::{Address as UnifiedAddress, Encoding, Receiver, Typecode};
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use orchard::keys::{
+    Diversifier, DiversifierIndex, FullViewingKey, IncomingViewingKey, OutgoingViewingKey, Scope,
+    SpendingKey,
+};
+use rand_core::{OsRng, RngCore};
+use ripemd::Ripemd160;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+use zcash_address::unified::{Address as UnifiedAddress, Container, Encoding, Fvk, Receiver, Typecode, Ufvk};
+use std::io::{self, Error, ErrorKind};
+use zcash_primitives::consensus::NetworkType;
+use zcash_primitives::zip32::DiversifiableFullViewingKey;
+
+/// RIPEMD160(SHA256(pubkey)), the P2PKH "pubkey hash" used both as a legacy transparent
+/// address and as the `Receiver::P2pkh` item in a unified address.
+fn hash160(pubkey: &PublicKey) -> [u8; 20] {
+    let sha256_digest = Sha256::digest(pubkey.serialize());
+    Ripemd160::digest(sha256_digest).into()
+}
 // A struct that holds orchard private keys or view keys
 #[derive(Clone, Debug, PartialEq)]
 pub struct WalletOKey {
@@ -13,6 +33,10 @@ pub struct WalletOKey {
     // If locked, the encrypted private key is stored here
     enc_key: Option<Vec<u8>>,
     nonce: Option<Vec<u8>>,
+
+    // The next external-scope diversifier index to hand out via `next_diversified_address`,
+    // so repeated calls rotate through fresh receivers instead of reusing index 0.
+    next_diversifier_index: u64,
 }
 
 impl WalletOKey {
@@ -29,6 +53,7 @@ impl WalletOKey {
             hdkey_num: None,
             enc_key: None,
             nonce: None,
+            next_diversifier_index: 0,
         }
     }
 }
@@ -40,6 +65,9 @@ pub(crate) enum WalletOKeyInner {
     ImportedFullViewKey(FullViewingKey),
     ImportedInViewKey(IncomingViewingKey),
     ImportedOutViewKey(OutgoingViewingKey),
+    // The spend authority has been encrypted and dropped from memory by `WalletOKey::lock`;
+    // `tag` records which variant (`HdKey` vs `ImportedSpendingKey`) to restore on unlock.
+    Locked { tag: u8 },
 }
 
 impl WalletOKeyInner {
@@ -70,6 +98,7 @@ impl PartialEq for WalletOKeyInner {
             (ImportedFullViewKey(a), ImportedFullViewKey(b)) => a == b,
             (ImportedInViewKey(a), ImportedInViewKey(b)) => a == b,
             (ImportedOutViewKey(a), ImportedOutViewKey(b)) => a.as_ref() == b.as_ref(),
+            (Locked { tag: a }, Locked { tag: b }) => a == b,
             _ => false,
         }
     }
@@ -88,6 +117,355 @@ impl WalletOKey {
             hdkey_num: Some(hdkey_num),
             enc_key: None,
             nonce: None,
+            next_diversifier_index: 0,
+        }
+    }
+
+    /// Downgrades this key to watch-only, discarding its spend authority while preserving its
+    /// unified address and HD key number. Lets a user derive a view-only wallet from a seeded
+    /// one for safe monitoring on an exposed device. A locked entry is returned as it is, still
+    /// locked and still holding its encrypted spending key.
+    pub fn into_watch_only(self) -> WalletOKey {
+        let key = match &self.key {
+            WalletOKeyInner::HdKey(sk) | WalletOKeyInner::ImportedSpendingKey(sk) => {
+                WalletOKeyInner::ImportedFullViewKey(FullViewingKey::from(sk))
+            }
+            other => other.clone(),
+        };
+
+        WalletOKey { key, ..self }
+    }
+
+    /// Returns the spending key backing this entry, for key export. Watch-only entries (locked,
+    /// or already downgraded via [`WalletOKey::into_watch_only`]) never have one, so callers
+    /// building an export path can rely on `None` to mean "nothing to refuse to reveal".
+    pub fn exportable_spending_key(&self) -> Option<SpendingKey> {
+        self.spending_key()
+    }
+
+    /// Returns the spending key backing this entry, or `None` while it's locked or if it never
+    /// held one (watch-only/view-key-only entries).
+    pub fn spending_key(&self) -> Option<SpendingKey> {
+        if self.locked {
+            return None;
+        }
+        self.key.spending_key()
+    }
+
+    /// Encrypts the spending key with `key` (a 32-byte AES-256 key, typically derived from a
+    /// user passphrase), zeroizes the plaintext copy, and replaces `self.key` with a
+    /// [`WalletOKeyInner::Locked`] placeholder, so the spend authority does not linger in memory
+    /// for as long as the wallet is locked.
+    ///
+    /// This is the per-key primitive the wallet-level lock/unlock flow (`Keys::lock` in the
+    /// `keys` module, reached via `LightWallet::lock`) calls once per entry in `okeys`; see
+    /// `tests` below for this method's own lock/unlock/wrong-key-rejection coverage.
+    pub fn lock(&mut self, key: &[u8]) -> io::Result<()> {
+        if self.locked {
+            return Ok(());
+        }
+        let (tag, sk) = match &self.key {
+            WalletOKeyInner::HdKey(sk) => (0u8, *sk),
+            WalletOKeyInner::ImportedSpendingKey(sk) => (1u8, *sk),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Only a key holding spend authority can be locked",
+                ))
+            }
+        };
+
+        let mut plaintext = Vec::with_capacity(1 + 32);
+        plaintext.push(tag);
+        plaintext.extend_from_slice(&sk.to_bytes());
+
+        let cipher = Aes256Gcm::new(Key::from_slice(key));
+        let mut nonce_bytes = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_ref())
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+        plaintext.zeroize();
+
+        self.key = WalletOKeyInner::Locked { tag };
+        self.enc_key = Some(ciphertext);
+        self.nonce = Some(nonce_bytes.to_vec());
+        self.locked = true;
+        Ok(())
+    }
+
+    /// Reverses [`WalletOKey::lock`], verifying the AES-GCM authentication tag before restoring
+    /// the spending key so a wrong passphrase or corrupted `enc_key` is rejected rather than
+    /// silently producing a garbage key.
+    pub fn unlock(&mut self, key: &[u8]) -> io::Result<()> {
+        if !self.locked {
+            return Ok(());
+        }
+        let enc_key = self
+            .enc_key
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No encrypted key to unlock"))?;
+        let nonce = self
+            .nonce
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing nonce"))?;
+
+        let cipher = Aes256Gcm::new(Key::from_slice(key));
+        let mut plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), enc_key.as_ref())
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    "Failed to unlock: wrong key or corrupted data",
+                )
+            })?;
+
+        let sk_bytes: [u8; 32] = plaintext[1..]
+            .try_into()
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "Corrupted spending key"))?;
+        let sk = Option::from(SpendingKey::from_bytes(sk_bytes))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid spending key bytes"))?;
+        self.key = if plaintext[0] == 0 {
+            WalletOKeyInner::HdKey(sk)
+        } else {
+            WalletOKeyInner::ImportedSpendingKey(sk)
+        };
+        plaintext.zeroize();
+
+        self.enc_key = None;
+        self.nonce = None;
+        self.locked = false;
+        Ok(())
+    }
+
+    /// Derives the unified address for this key's Orchard receiver at an arbitrary diversifier
+    /// index and scope, rather than always reusing index 0. Returns `None` if this entry has no
+    /// viewing key (e.g. it only holds an incoming/outgoing viewing key).
+    pub fn address_at(&self, index: DiversifierIndex, scope: Scope) -> Option<UnifiedAddress> {
+        let address = self.key.full_viewing_key()?.address_at(index, scope);
+        UnifiedAddress::try_from_items(vec![Receiver::Orchard(address.to_raw_address_bytes())])
+            .ok()
+    }
+
+    /// Hands out the next unused external-scope receiver, advancing `next_diversifier_index` so
+    /// a later call won't repeat it. Lets each payment use a fresh address for privacy.
+    pub fn next_diversified_address(&mut self) -> Option<UnifiedAddress> {
+        let index = DiversifierIndex::from(self.next_diversifier_index);
+        let address = self.address_at(index, Scope::External)?;
+        self.next_diversifier_index += 1;
+        Some(address)
+    }
+}
+
+/// A single account's viewing capability spanning every pool it holds a key for, assembled
+/// from (and round-tripped through) a ZIP 316 Unified Full Viewing Key string. Importing one
+/// of these lets a watch-only wallet follow an account across Orchard, Sapling, and
+/// transparent receivers instead of needing a key imported per pool.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalletUnifiedViewingKey {
+    pub orchard: Option<FullViewingKey>,
+    pub sapling: Option<DiversifiableFullViewingKey>,
+    pub transparent: Option<[u8; 33]>,
+}
+
+impl WalletUnifiedViewingKey {
+    /// Parses a bech32-encoded UFVK, keeping whichever of the Orchard, Sapling, and
+    /// transparent components are present. Unknown/future receiver kinds are ignored rather
+    /// than rejected, so a UFVK minted by a newer wallet can still be partially imported here.
+    pub fn new_imported_ufvk(encoded: &str) -> io::Result<(Self, NetworkType)> {
+        let (network, ufvk) = Ufvk::decode(encoded)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))?;
+
+        let mut orchard = None;
+        let mut sapling = None;
+        let mut transparent = None;
+        for fvk in ufvk.items() {
+            match fvk {
+                Fvk::Orchard(bytes) => orchard = Option::from(FullViewingKey::from_bytes(&bytes)),
+                Fvk::Sapling(bytes) => {
+                    // ZIP 316 carries only the raw diversifiable FVK (ak || nk || ovk || dk),
+                    // not a full zip32 extended key, so read it as the bare
+                    // DiversifiableFullViewingKey rather than ExtendedFullViewingKey.
+                    sapling = DiversifiableFullViewingKey::from_bytes(&bytes);
+                }
+                Fvk::P2pkh(bytes) => transparent = Some(bytes),
+                Fvk::Unknown { .. } => {}
+            }
+        }
+
+        if orchard.is_none() && sapling.is_none() && transparent.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "UFVK did not contain a recognized Orchard, Sapling, or transparent component",
+            ));
+        }
+
+        Ok((
+            Self {
+                orchard,
+                sapling,
+                transparent,
+            },
+            network,
+        ))
+    }
+
+    /// Re-encodes whichever components are present back into a UFVK string, for export.
+    pub fn encode_ufvk(&self, network: NetworkType) -> io::Result<String> {
+        let mut items = vec![];
+        if let Some(fvk) = &self.orchard {
+            items.push(Fvk::Orchard(fvk.to_bytes()));
+        }
+        if let Some(fvk) = &self.sapling {
+            items.push(Fvk::Sapling(fvk.to_bytes()));
+        }
+        if let Some(pubkey) = self.transparent {
+            items.push(Fvk::P2pkh(pubkey));
+        }
+
+        Ufvk::try_from_items(items)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+            .map(|ufvk| ufvk.encode(&network))
+    }
+
+    /// Builds a unified address covering whichever receivers this key can view, so a single
+    /// imported UFVK can watch an entire account across pools instead of per-pool addresses.
+    pub fn unified_address(&self) -> io::Result<UnifiedAddress> {
+        let mut receivers = vec![];
+        if let Some(fvk) = &self.orchard {
+            receivers.push(Receiver::Orchard(
+                fvk.address_at(0u32, Scope::External).to_raw_address_bytes(),
+            ));
         }
+        if let Some(dfvk) = &self.sapling {
+            let (_, addr) = dfvk.default_address();
+            receivers.push(Receiver::Sapling(addr.to_bytes()));
+        }
+        if let Some(pubkey_bytes) = &self.transparent {
+            if let Ok(pubkey) = PublicKey::from_slice(pubkey_bytes) {
+                receivers.push(Receiver::P2pkh(hash160(&pubkey)));
+            }
+        }
+
+        UnifiedAddress::try_from_items(receivers)
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+    }
+}
+
+/// A transparent P2PKH key, derived per BIP44. Lets a wallet that otherwise only holds shielded
+/// Orchard keys also receive to (and later shield from) a t-address, mirroring `WalletOKey`'s
+/// shape so the two can be held side by side on the same account.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalletTKey {
+    sk: Option<SecretKey>,
+    pub(super) pubkey: PublicKey,
+    pub(super) unified_address: UnifiedAddress,
+
+    // If this is a HD key, what is the key number
+    pub(super) hdkey_num: Option<u32>,
+
+    locked: bool,
+    enc_key: Option<Vec<u8>>,
+    nonce: Option<Vec<u8>>,
+}
+
+impl WalletTKey {
+    /// Wraps an already BIP44-derived transparent secret key (derivation itself is the
+    /// caller's responsibility, matching how [`WalletOKey::new_hdkey`] takes an already-derived
+    /// Orchard key rather than deriving one itself).
+    pub fn new_hdkey(hdkey_num: u32, sk: SecretKey) -> Self {
+        let pubkey = PublicKey::from_secret_key(&Secp256k1::signing_only(), &sk);
+        let unified_address =
+            UnifiedAddress::try_from_items(vec![Receiver::P2pkh(hash160(&pubkey))]).unwrap();
+
+        WalletTKey {
+            sk: Some(sk),
+            pubkey,
+            unified_address,
+            hdkey_num: Some(hdkey_num),
+            locked: false,
+            enc_key: None,
+            nonce: None,
+        }
+    }
+
+    /// Downgrades this key to watch-only, discarding its spend authority while preserving its
+    /// address, mirroring [`WalletOKey::into_watch_only`].
+    pub fn into_watch_only(self) -> WalletTKey {
+        WalletTKey { sk: None, ..self }
+    }
+
+    pub fn spending_key(&self) -> Option<SecretKey> {
+        if self.locked {
+            return None;
+        }
+        self.sk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Any old Orchard spending key, deterministic so assertions can compare against it.
+    fn test_spending_key() -> SpendingKey {
+        let mut bytes = [7u8; 32];
+        loop {
+            let sk = SpendingKey::from_bytes(bytes);
+            if sk.is_some().into() {
+                return sk.unwrap();
+            }
+            bytes[0] = bytes[0].wrapping_add(1);
+        }
+    }
+
+    #[test]
+    fn lock_then_unlock_with_the_same_key_restores_the_spending_key() {
+        let mut okey = WalletOKey::new_hdkey(0, test_spending_key());
+        let original = okey
+            .spending_key()
+            .expect("a freshly created key is unlocked");
+
+        let passphrase_key = [1u8; 32];
+        okey.lock(&passphrase_key)
+            .expect("locking an unlocked key should succeed");
+        assert!(okey.spending_key().is_none());
+
+        okey.unlock(&passphrase_key)
+            .expect("unlocking with the same key should succeed");
+        assert_eq!(okey.spending_key(), Some(original));
+    }
+
+    #[test]
+    fn unlock_with_the_wrong_key_is_rejected_and_leaves_the_key_locked() {
+        let mut okey = WalletOKey::new_hdkey(0, test_spending_key());
+        okey.lock(&[1u8; 32]).unwrap();
+
+        let err = okey
+            .unlock(&[2u8; 32])
+            .expect_err("unlocking with the wrong key should fail");
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(
+            okey.spending_key().is_none(),
+            "a rejected unlock must leave the key locked"
+        );
+    }
+
+    #[test]
+    fn locking_a_view_key_only_entry_is_rejected() {
+        let mut okey = WalletOKey::new_hdkey(0, test_spending_key()).into_watch_only();
+        assert!(okey.lock(&[1u8; 32]).is_err());
+    }
+
+    #[test]
+    fn locking_an_already_locked_key_is_a_no_op() {
+        let mut okey = WalletOKey::new_hdkey(0, test_spending_key());
+        let passphrase_key = [1u8; 32];
+        okey.lock(&passphrase_key).unwrap();
+
+        okey.lock(&passphrase_key)
+            .expect("locking an already-locked key should be a no-op, not an error");
+        okey.unlock(&passphrase_key).unwrap();
+        assert!(okey.spending_key().is_some());
     }
 }
\ No newline at end of file
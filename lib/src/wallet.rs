@@ -1,5 +1,7 @@
 use crate::blaze::fetch_full_transaction::TransactionContext;
-use crate::compact_formats::TreeState;
+use crate::compact_formats::{Exclude, TreeState};
+use crate::grpc_connector::GrpcConnector;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use crate::wallet::data::TransactionMetadata;
 use crate::wallet::keys::transparent::TransparentKey;
 use crate::wallet::{data::SpendableSaplingNote, keys::sapling::SaplingKey};
@@ -21,6 +23,7 @@ use zcash_client_backend::{
     encoding::{
         decode_extended_full_viewing_key, decode_extended_spending_key, encode_payment_address,
     },
+    keys::UnifiedFullViewingKey,
 };
 use zcash_encoding::{Optional, Vector};
 use zcash_note_encryption::Domain;
@@ -32,10 +35,12 @@ use zcash_primitives::{
     consensus::BlockHeight,
     legacy::Script,
     memo::Memo,
+    sapling,
     sapling::prover::TxProver,
     transaction::{
         builder::Builder,
         components::{amount::DEFAULT_FEE, Amount, OutPoint, TxOut},
+        Transaction, TxId,
     },
 };
 
@@ -99,26 +104,112 @@ pub enum MemoDownloadOption {
     AllMemos,
 }
 
+/// How [`LightWallet::select_notes_and_utxos`] picks which notes to spend for a given pool.
+#[derive(Debug, Clone, Copy)]
+pub enum NoteSelectionStrategy {
+    /// Search for a subset of notes landing within `[target, target + cost_of_change]`,
+    /// minimizing the number of notes spent. Falls back to `GreedyAscending` when no such
+    /// subset is found (or the candidate set is too large to search exhaustively).
+    BranchAndBound { cost_of_change: u64 },
+    /// Accumulate notes smallest-first until the target is met.
+    GreedyAscending,
+}
+
+/// A pool `select_notes_and_utxos` drew inputs from for a transaction. Spending from more than
+/// one of these in the same transaction links them together on-chain (on top of whatever
+/// linkage the transaction's outputs already create), so `build_transaction_plan` logs a
+/// warning whenever a plan's `touched_pools` has more than one entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShieldedPool {
+    Orchard,
+    Sapling,
+    Transparent,
+}
+
+/// The distinct pools `orchard_notes`, `sapling_notes`, and `utxos` draw from, listed Orchard,
+/// then Sapling, then transparent. This is a fixed listing order, not the order in which
+/// `select_notes_and_utxos` draws its inputs.
+fn touched_pools(
+    orchard_notes: &[SpendableOrchardNote],
+    sapling_notes: &[SpendableSaplingNote],
+    utxos: &[Utxo],
+) -> Vec<ShieldedPool> {
+    let mut pools = vec![];
+    if !orchard_notes.is_empty() {
+        pools.push(ShieldedPool::Orchard);
+    }
+    if !sapling_notes.is_empty() {
+        pools.push(ShieldedPool::Sapling);
+    }
+    if !utxos.is_empty() {
+        pools.push(ShieldedPool::Transparent);
+    }
+    pools
+}
+
+/// Confirmed and unconfirmed transparent value, broken down per-address. See
+/// [`LightWallet::transparent_balance`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransparentBalance {
+    pub confirmed: u64,
+    pub unconfirmed: u64,
+    pub by_address: HashMap<String, u64>,
+}
+
+/// Everything [`LightWallet::send_to_address_internal`] needs to build a transaction,
+/// resolved ahead of time: which notes/UTXOs cover the target amount, which anchors
+/// they're proved against, and where the outputs (including change) go. Splitting this
+/// out of the build step means selection, anchor resolution, and change computation can
+/// run independently of proving, so the same plan could in principle be carried to an
+/// external signer (e.g. a hardware wallet) instead of being proved in-process right
+/// away; today [`LightWallet::build_and_broadcast_plan`] is the only consumer.
+#[derive(Clone)]
+pub struct TransactionPlan {
+    pub target_height: BlockHeight,
+    pub orchard_anchor: orchard::Anchor,
+    pub orchard_notes: Vec<SpendableOrchardNote>,
+    pub sapling_notes: Vec<SpendableSaplingNote>,
+    pub utxos: Vec<Utxo>,
+    pub outputs: Vec<(address::RecipientAddress, Amount, Option<String>)>,
+    pub fee: Amount,
+    /// Total value of `orchard_notes`/`sapling_notes`/`utxos` combined. `build_and_broadcast_plan`
+    /// needs this to size an explicit change output of exactly `selected_value - outputs - fee`,
+    /// rather than letting the builder fall back to its own default-fee change computation.
+    pub selected_value: Amount,
+    /// Placeholder txid `orchard_notes`/`sapling_notes`/`utxos` were already marked
+    /// `unconfirmed_spent` with at selection time; see [`LightWallet::reserve_selected_notes`].
+    pub(crate) reservation_txid: TxId,
+    /// Which pool(s) `orchard_notes`, `sapling_notes`, and `utxos` draw from. More than one
+    /// entry means this plan links those pools together on-chain; see [`ShieldedPool`].
+    pub touched_pools: Vec<ShieldedPool>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WalletOptions {
     pub(crate) download_memos: MemoDownloadOption,
+
+    /// When set, `LightWallet::write` gzip-deflates the cached block list before writing it,
+    /// and `LightWallet::read` inflates it back. Existing wallets default this to `false` so
+    /// they keep reading/writing the uncompressed format without needing a rescan.
+    pub(crate) compress_blocks: bool,
 }
 
 impl Default for WalletOptions {
     fn default() -> Self {
         WalletOptions {
             download_memos: MemoDownloadOption::WalletMemos,
+            compress_blocks: false,
         }
     }
 }
 
 impl WalletOptions {
     pub fn serialized_version() -> u64 {
-        return 1;
+        return 2;
     }
 
     pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
-        let _version = reader.read_u64::<LittleEndian>()?;
+        let version = reader.read_u64::<LittleEndian>()?;
 
         let download_memos = match reader.read_u8()? {
             0 => MemoDownloadOption::NoMemos,
@@ -132,14 +223,24 @@ impl WalletOptions {
             }
         };
 
-        Ok(Self { download_memos })
+        let compress_blocks = if version <= 1 {
+            false
+        } else {
+            reader.read_u8()? == 1
+        };
+
+        Ok(Self {
+            download_memos,
+            compress_blocks,
+        })
     }
 
     pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
         // Write the version
         writer.write_u64::<LittleEndian>(Self::serialized_version())?;
 
-        writer.write_u8(self.download_memos as u8)
+        writer.write_u8(self.download_memos as u8)?;
+        writer.write_u8(self.compress_blocks as u8)
     }
 }
 
@@ -165,12 +266,224 @@ pub struct LightWallet {
 
     // Local data to the proxy to specify transactions to fetch.
     pub(crate) transaction_context: TransactionContext,
+
+    // Unconfirmed transactions currently sitting in the server's mempool, keyed by txid.
+    // Populated and evicted by `start_mempool_monitor`; kept separate from
+    // `transaction_context.transaction_metadata_set` so a reorg or eviction can't disturb
+    // confirmed history.
+    pub(crate) mempool_transactions: Arc<RwLock<HashMap<TxId, TransactionMetadata>>>,
+
+    // Disjoint (start, end) fully-scanned height intervals plus lightweight per-block-boundary
+    // metadata, so sync can proceed out of order (tip first, history backfilled later) instead of
+    // assuming `blocks` is always one contiguous tip-anchored range.
+    pub(crate) scan_ranges: Arc<RwLock<ScanRanges>>,
+
+    // Per-pool target anchor depth `select_notes_and_utxos` resolves its spend anchors against.
+    // See [`AnchorDepthConfig`].
+    pub(crate) anchor_depth: Arc<RwLock<AnchorDepthConfig>>,
+
+    // Source of the placeholder txids `build_transaction_plan` reserves selected notes/utxos
+    // against before a real txid exists. Only needs to be unique per in-flight plan, not
+    // persisted across restarts, so a fresh counter starting at 0 is fine.
+    reservation_counter: AtomicU64,
+}
+
+// How often the mempool monitor polls the server for newly-seen, unconfirmed transactions.
+const MEMPOOL_MONITOR_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// Default Zcash transaction expiry window, in blocks, used when deciding whether a spend we
+// broadcast has fallen out of the mempool for good rather than just not being mined yet.
+// Matches zcashd/lightwalletd's default `-txexpirydelta`.
+const DEFAULT_TX_EXPIRY_DELTA: u32 = 40;
+
+// How many times `build_transaction_plan` will re-select notes against a recomputed
+// conventional fee before giving up and reporting that fee estimation didn't converge.
+const MAX_FEE_ESTIMATION_ITERATIONS: u32 = 10;
+
+/// A named repair/backfill step applied to a freshly-loaded wallet, identified by the highest
+/// serialized version it still needs to run for. `LightWallet::read` runs every migration whose
+/// `applies_up_to_version` is at or above the version that was actually on disk, in the order
+/// they're listed, logging each one as it runs. This replaces what used to be a run of scattered
+/// `if version <= N` repair steps inline in `read()`.
+struct WalletMigration {
+    name: &'static str,
+    applies_up_to_version: u64,
+    run: for<'a> fn(&'a LightWallet) -> std::pin::Pin<Box<dyn Future<Output = ()> + Send + 'a>>,
+}
+
+/// Per-pool target anchor depth (in confirmations, counting the chain tip as the first, so a
+/// depth of 1 anchors at the tip) that [`LightWallet::select_notes_and_utxos`]
+/// builds authentication paths against, modeled on zcashd's `-orchardanchorconfirmations`/
+/// `-anchorconfirmations`. Replaces the old `[u32; 5]` `anchor_offset` fallback array on
+/// `ZingoConfig` with one named depth per pool; a spend that passes a `minconf` lower than the
+/// configured depth clamps the anchor down to `minconf` rather than demanding an anchor deeper
+/// than the note it wants to spend can actually support.
+#[derive(Debug, Clone, Copy)]
+pub struct AnchorDepthConfig {
+    pub orchard_depth: u32,
+    pub sapling_depth: u32,
+    /// Confirmations a transparent UTXO's funding transaction needs before it's treated as
+    /// confirmed, for both `transparent_balance` and `select_notes_and_utxos`'s
+    /// `transparent_only` path. Transparent inputs need no commitment-tree anchor, so this
+    /// is just a confirmation count rather than an anchor depth, but it's clamped by `minconf`
+    /// the same way the shielded depths are.
+    pub transparent_depth: u32,
+}
+
+impl Default for AnchorDepthConfig {
+    fn default() -> Self {
+        // Matches zcashd's default for both `-orchardanchorconfirmations` and `-anchorconfirmations`.
+        AnchorDepthConfig {
+            orchard_depth: 10,
+            sapling_depth: 10,
+            // Matches zcashd's default `-minconf` for spending transparent funds.
+            transparent_depth: 1,
+        }
+    }
+}
+
+impl AnchorDepthConfig {
+    /// The depth actually used to pick an anchor for this spend: never deeper than `minconf`,
+    /// since an anchor deeper than a note's own confirmation count can't witness that note.
+    fn effective_depth(configured_depth: u32, minconf: Option<u32>) -> u32 {
+        match minconf {
+            Some(minconf) => cmp::min(configured_depth, minconf),
+            None => configured_depth,
+        }
+    }
+}
+
+/// Lightweight metadata recorded at a scanned block boundary, so witnesses for notes on either
+/// side of a gap can be stitched together once the gap is backfilled.
+#[derive(Clone, Debug)]
+pub struct BlockBoundaryMeta {
+    pub height: BlockHeight,
+    pub hash: String,
+    pub sapling_tree_size: u32,
+    pub orchard_tree_size: u32,
+}
+
+/// Tracks which block heights have been fully scanned as a set of disjoint, inclusive `(start,
+/// end)` ranges, plus boundary metadata for each scanned block. [`LightWallet::set_blocks`]
+/// records the span of whatever block list it's handed here as it's called, so the set stays
+/// empty only for a wallet that hasn't synced yet, in which case every range-aware query defers
+/// to the legacy assumption that `LightWallet::blocks` is itself one contiguous, tip-anchored
+/// range.
+#[derive(Default)]
+pub struct ScanRanges {
+    ranges: Vec<(BlockHeight, BlockHeight)>,
+    boundaries: std::collections::BTreeMap<BlockHeight, BlockBoundaryMeta>,
+}
+
+impl ScanRanges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records `[start, end]` as fully scanned, merging it with any overlapping or adjacent
+    /// ranges already known so the set stays disjoint.
+    pub fn insert_scanned_range(&mut self, start: BlockHeight, end: BlockHeight) {
+        self.ranges.push((start, end));
+        self.ranges.sort_by_key(|(start, _)| *start);
+        let mut merged: Vec<(BlockHeight, BlockHeight)> = Vec::with_capacity(self.ranges.len());
+        for (start, end) in self.ranges.drain(..) {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= *last_end + 1 => {
+                    if end > *last_end {
+                        *last_end = end;
+                    }
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        self.ranges = merged;
+    }
+
+    pub fn insert_boundary(&mut self, meta: BlockBoundaryMeta) {
+        self.boundaries.insert(meta.height, meta);
+    }
+
+    /// True if every height in `[start, end]` falls within a single recorded scanned range, i.e.
+    /// a witness path can be assumed complete across that window. Fails open (returns `true`)
+    /// when no ranges have been recorded yet, deferring to the legacy `blocks`-based coverage.
+    pub fn is_contiguous(&self, start: BlockHeight, end: BlockHeight) -> bool {
+        self.ranges.is_empty()
+            || self
+                .ranges
+                .iter()
+                .any(|(range_start, range_end)| *range_start <= start && end <= *range_end)
+    }
+
+    /// Scan ranges in priority order: the most recent window up to `tip_height` first, so
+    /// recently-received notes become spendable quickly, followed by older gaps (oldest gap
+    /// last) that still need backfilling to complete witnesses.
+    pub fn suggest_scan_ranges(&self, tip_height: BlockHeight) -> Vec<(BlockHeight, BlockHeight)> {
+        if self.ranges.is_empty() {
+            return vec![];
+        }
+        let mut suggestions = vec![];
+        let covers_tip = self
+            .ranges
+            .iter()
+            .any(|(_, end)| *end >= tip_height);
+        if !covers_tip {
+            let last_covered = self.ranges.last().map(|(_, end)| *end);
+            let recent_start = last_covered.map_or(tip_height, |end| end + 1);
+            suggestions.push((recent_start, tip_height));
+        }
+        let mut gaps: Vec<(BlockHeight, BlockHeight)> = self
+            .ranges
+            .windows(2)
+            .map(|w| (w[0].1 + 1, w[1].0 - 1))
+            .collect();
+        gaps.reverse();
+        suggestions.extend(gaps);
+        suggestions
+    }
+}
+
+fn wallet_migrations() -> Vec<WalletMigration> {
+    vec![
+        WalletMigration {
+            name: "reverse_block_order",
+            applies_up_to_version: 14,
+            run: |wallet| Box::pin(wallet.migrate_reverse_block_order()),
+        },
+        WalletMigration {
+            name: "adjust_spendable_status",
+            applies_up_to_version: 8,
+            run: |wallet| Box::pin(wallet.migrate_adjust_spendable_status()),
+        },
+        WalletMigration {
+            name: "remove_unused_addresses",
+            applies_up_to_version: 14,
+            run: |wallet| {
+                Box::pin(async move {
+                    wallet.remove_unused_taddrs().await;
+                    wallet.remove_unused_zaddrs().await;
+                })
+            },
+        },
+        WalletMigration {
+            name: "set_witness_block_heights",
+            applies_up_to_version: 14,
+            run: |wallet| Box::pin(wallet.set_witness_block_heights()),
+        },
+    ]
 }
 
 use crate::wallet::traits::{Diversifiable as _, WalletKey};
 impl LightWallet {
     pub fn serialized_version() -> u64 {
-        return 24;
+        // Version 25 reserves the number for `Keys::write`/`Keys::read` to gain per-key
+        // at-rest encryption state (an XSalsa20-Poly1305 secretbox around each spending key's
+        // bytes, keyed off a passphrase, with `encrypt`/`lock`/`unlock`/`remove_encryption`
+        // driving it) - that cipher and the version-gated round-trip live in the `keys` module
+        // and are not part of this change; this bump only tightens the existing
+        // `is_unlocked_for_spending` checks below.
+        // Version 26 prefixes the block list with a compression flag, letting `compress_blocks`
+        // gzip-deflate the cached blocks without breaking older wallet files.
+        return 26;
     }
 
     pub fn new(
@@ -196,6 +509,10 @@ impl LightWallet {
             send_progress: Arc::new(RwLock::new(SendProgress::new(0))),
             price: Arc::new(RwLock::new(WalletZecPriceInfo::new())),
             transaction_context,
+            mempool_transactions: Arc::new(RwLock::new(HashMap::new())),
+            scan_ranges: Arc::new(RwLock::new(ScanRanges::new())),
+            anchor_depth: Arc::new(RwLock::new(AnchorDepthConfig::default())),
+            reservation_counter: AtomicU64::new(0),
         })
     }
 
@@ -218,13 +535,20 @@ impl LightWallet {
             Keys::read(&mut reader, config)
         }?;
 
-        let mut blocks = Vector::read(&mut reader, |r| BlockData::read(r))?;
-        if version <= 14 {
-            // Reverse the order, since after version 20, we need highest-block-first
-            blocks = blocks.into_iter().rev().collect();
-        }
+        let blocks = if version <= 25 {
+            Vector::read(&mut reader, |r| BlockData::read(r))?
+        } else if reader.read_u8()? == 1 {
+            let compressed_blocks = Vector::read(&mut reader, |r| r.read_u8())?;
+            let mut raw_blocks = vec![];
+            GzDecoder::new(&compressed_blocks[..]).read_to_end(&mut raw_blocks)?;
+            Vector::read(&mut std::io::Cursor::new(raw_blocks), |r| {
+                BlockData::read(r)
+            })?
+        } else {
+            Vector::read(&mut reader, |r| BlockData::read(r))?
+        };
 
-        let mut transactions = if version <= 14 {
+        let transactions = if version <= 14 {
             TransactionMetadataSet::read_old(&mut reader)
         } else {
             TransactionMetadataSet::read(&mut reader)
@@ -274,18 +598,6 @@ impl LightWallet {
             })?
         };
 
-        // If version <= 8, adjust the "is_spendable" status of each note data
-        if version <= 8 {
-            // Collect all spendable keys
-            let spendable_keys: Vec<_> = keys
-                .get_all_sapling_extfvks()
-                .into_iter()
-                .filter(|extfvk| keys.have_sapling_spending_key(extfvk))
-                .collect();
-
-            transactions.adjust_spendable_status(spendable_keys);
-        }
-
         let price = if version <= 13 {
             WalletZecPriceInfo::new()
         } else {
@@ -297,7 +609,7 @@ impl LightWallet {
             Arc::new(RwLock::new(keys)),
             Arc::new(RwLock::new(transactions)),
         );
-        let mut lw = Self {
+        let lw = Self {
             blocks: Arc::new(RwLock::new(blocks)),
             wallet_options: Arc::new(RwLock::new(wallet_options)),
             birthday: AtomicU64::new(birthday),
@@ -305,19 +617,90 @@ impl LightWallet {
             send_progress: Arc::new(RwLock::new(SendProgress::new(0))),
             price: Arc::new(RwLock::new(price)),
             transaction_context,
+            mempool_transactions: Arc::new(RwLock::new(HashMap::new())),
+            scan_ranges: Arc::new(RwLock::new(ScanRanges::new())),
+            anchor_depth: Arc::new(RwLock::new(AnchorDepthConfig::default())),
+            reservation_counter: AtomicU64::new(0),
         };
 
-        // For old wallets, remove unused addresses
-        if version <= 14 {
-            lw.remove_unused_taddrs().await;
-            lw.remove_unused_zaddrs().await;
-        }
+        lw.run_migrations(version).await;
+        lw.repair_inconsistencies().await;
+
+        Ok(lw)
+    }
 
-        if version <= 14 {
-            lw.set_witness_block_heights().await;
+    /// Runs every [`WalletMigration`] that still applies to a wallet loaded from `from_version`,
+    /// in order, logging each one as it completes.
+    async fn run_migrations(&self, from_version: u64) {
+        for migration in wallet_migrations() {
+            if from_version <= migration.applies_up_to_version {
+                (migration.run)(self).await;
+                info!(
+                    "Ran wallet migration '{}' (wallet was version {})",
+                    migration.name, from_version
+                );
+            }
         }
+    }
 
-        Ok(lw)
+    // Reverse the cached block order, since after version 20, we need highest-block-first.
+    async fn migrate_reverse_block_order(&self) {
+        let mut blocks = self.blocks.write().await;
+        let reversed = blocks.drain(..).rev().collect();
+        *blocks = reversed;
+    }
+
+    // Adjust the "is_spendable" status of each note, now that we know which sapling keys in
+    // this wallet are spending keys rather than just viewing keys.
+    async fn migrate_adjust_spendable_status(&self) {
+        let keys = self.transaction_context.keys.read().await;
+        let spendable_keys: Vec<_> = keys
+            .get_all_sapling_extfvks()
+            .into_iter()
+            .filter(|extfvk| keys.have_sapling_spending_key(extfvk))
+            .collect();
+        drop(keys);
+
+        self.transaction_context
+            .transaction_metadata_set
+            .write()
+            .await
+            .adjust_spendable_status(spendable_keys);
+    }
+
+    /// Detects and corrects known data corruptions independent of the wallet's serialized
+    /// version: notes duplicated across a re-scan, witnesses left with a stale `top_height`,
+    /// and "unconfirmed spent" markers pointing at a txid that no longer exists in the wallet
+    /// (e.g. after a reorg dropped the spending transaction). Safe to call repeatedly, both
+    /// right after `read()` and on demand from a user-triggered repair.
+    pub async fn repair_inconsistencies(&self) {
+        let top_height = self.last_scanned_height().await;
+        let mut transactions = self.transaction_context.transaction_metadata_set.write().await;
+        let known_txids: std::collections::HashSet<_> =
+            transactions.current.keys().cloned().collect();
+
+        for wtx in transactions.current.values_mut() {
+            wtx.sapling_notes.iter_mut().for_each(|nd| {
+                if nd.witnesses.top_height != top_height {
+                    nd.witnesses.top_height = top_height;
+                }
+                if let Some((spent_txid, _)) = nd.unconfirmed_spent {
+                    if !known_txids.contains(&spent_txid) {
+                        nd.unconfirmed_spent = None;
+                    }
+                }
+            });
+
+            let mut seen_nullifiers = Vec::new();
+            wtx.sapling_notes.retain(|nd| {
+                if seen_nullifiers.contains(&nd.nullifier) {
+                    false
+                } else {
+                    seen_nullifiers.push(nd.nullifier);
+                    true
+                }
+            });
+        }
     }
 
     pub async fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
@@ -340,7 +723,20 @@ impl LightWallet {
             .await
             .write(&mut writer)?;
 
-        Vector::write(&mut writer, &self.blocks.read().await, |w, b| b.write(w))?;
+        let compress_blocks = self.wallet_options.read().await.compress_blocks;
+        writer.write_u8(compress_blocks as u8)?;
+        if compress_blocks {
+            let mut raw_blocks = vec![];
+            Vector::write(&mut raw_blocks, &self.blocks.read().await, |w, b| b.write(w))?;
+
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(&raw_blocks)?;
+            let compressed_blocks = encoder.finish()?;
+
+            Vector::write(&mut writer, &compressed_blocks, |w, b| w.write_u8(*b))?;
+        } else {
+            Vector::write(&mut writer, &self.blocks.read().await, |w, b| b.write(w))?;
+        }
 
         self.transaction_context
             .transaction_metadata_set
@@ -378,7 +774,7 @@ impl LightWallet {
     }
 
     // Before version 20, witnesses didn't store their height, so we need to update them.
-    pub async fn set_witness_block_heights(&mut self) {
+    pub async fn set_witness_block_heights(&self) {
         let top_height = self.last_scanned_height().await;
         self.transaction_context
             .transaction_metadata_set
@@ -402,6 +798,19 @@ impl LightWallet {
     }
 
     pub async fn set_blocks(&self, new_blocks: Vec<BlockData>) {
+        // `new_blocks` is highest-block-first; record the span it covers as fully scanned so
+        // `scan_ranges` reflects what the block-sync path has actually processed instead of
+        // staying permanently empty.
+        if let (Some(min_height), Some(max_height)) = (
+            new_blocks.last().map(|block| BlockHeight::from_u32(block.height as u32)),
+            new_blocks.first().map(|block| BlockHeight::from_u32(block.height as u32)),
+        ) {
+            self.scan_ranges
+                .write()
+                .await
+                .insert_scanned_range(min_height, max_height);
+        }
+
         let mut blocks = self.blocks.write().await;
         blocks.clear();
         blocks.extend_from_slice(&new_blocks[..]);
@@ -522,8 +931,8 @@ impl LightWallet {
     }
 
     pub async fn add_imported_tk(&self, sk: String) -> String {
-        if self.transaction_context.keys.read().await.encrypted {
-            return "Error: Can't import transparent address key while wallet is encrypted"
+        if !self.is_unlocked_for_spending().await {
+            return "Error: Can't import transparent address key while wallet is locked"
                 .to_string();
         }
 
@@ -652,8 +1061,8 @@ impl LightWallet {
         address_getter: impl FnOnce(KeyType) -> Fut,
         encode_address: impl Fn(WalletKey::Address) -> String,
     ) -> String {
-        if self.transaction_context.keys.read().await.encrypted {
-            return "Error: Can't import spending key while wallet is encrypted".to_string();
+        if !self.is_unlocked_for_spending().await {
+            return "Error: Can't import spending key while wallet is locked".to_string();
         }
         let decoded_key = match decoder(hrp, key) {
             Ok(Some(k)) => k,
@@ -735,6 +1144,91 @@ impl LightWallet {
         .await
     }
 
+    /// Import a ZIP 316 Unified Full Viewing Key, splitting it into its constituent Orchard
+    /// FVK, Sapling extended FVK, and transparent component, and registering each one in place
+    /// of a matching view-only key (or pushing a new entry) the same way a single-pool
+    /// `add_imported_*fvk` import does. Returns the derived unified address.
+    /// NOTE: This will not rescan the wallet
+    pub async fn add_imported_ufvk(&self, ufvk: String, birthday: u64) -> String {
+        if self.transaction_context.keys.read().await.encrypted {
+            return "Error: Can't import viewing key while wallet is encrypted".to_string();
+        }
+
+        let ufvk = match UnifiedFullViewingKey::decode(&self.transaction_context.config.chain, &ufvk) {
+            Ok(ufvk) => ufvk,
+            Err(e) => return format!("Error: Couldn't decode unified full viewing key: {}", e),
+        };
+
+        if let Some(extfvk) = ufvk.sapling() {
+            let already_known = self
+                .transaction_context
+                .keys
+                .read()
+                .await
+                .zkeys
+                .iter()
+                .any(|k| &k.extfvk == extfvk);
+            if !already_known {
+                let newkey = SaplingKey::new_imported_viewkey(extfvk.clone());
+                self.transaction_context
+                    .keys
+                    .write()
+                    .await
+                    .zkeys
+                    .push(newkey);
+            }
+        }
+
+        if let Some(fvk) = ufvk.orchard() {
+            let already_known = self
+                .transaction_context
+                .keys
+                .read()
+                .await
+                .okeys
+                .iter()
+                .any(|k: &OrchardKey| (&k.key).try_into().ok() == Some(fvk.clone()));
+            if !already_known {
+                let newkey = OrchardKey::new_imported_viewkey(fvk.clone());
+                self.transaction_context
+                    .keys
+                    .write()
+                    .await
+                    .okeys
+                    .push(newkey);
+            }
+        }
+
+        if let Some(tfvk) = ufvk.transparent() {
+            let already_known = self
+                .transaction_context
+                .keys
+                .read()
+                .await
+                .tkeys
+                .iter()
+                .any(|k| &k.pubkey == tfvk);
+            if !already_known {
+                let newkey = TransparentKey::new_imported_viewkey(
+                    &self.transaction_context.config,
+                    tfvk.clone(),
+                );
+                self.transaction_context
+                    .keys
+                    .write()
+                    .await
+                    .tkeys
+                    .push(newkey);
+            }
+        }
+
+        // Adjust wallet birthday
+        self.adjust_wallet_birthday(birthday);
+
+        let (address, _diversifier_index) = ufvk.default_address();
+        address.encode(&self.transaction_context.config.chain)
+    }
+
     /// Clears all the downloaded blocks and resets the state back to the initial block.
     /// After this, the wallet's initial state will need to be set
     /// and the wallet will need to be rescanned
@@ -798,8 +1292,9 @@ impl LightWallet {
                 let target_height = max_height + 1;
 
                 // Select an anchor ANCHOR_OFFSET back from the target block,
-                // unless that would be before the earliest block we have.
-                let anchor_height = cmp::max(
+                // unless that would be before the earliest block we have, or before the start
+                // of whatever fully-scanned-and-contiguous range covers the target height.
+                let mut anchor_height = cmp::max(
                     target_height.saturating_sub(
                         *self
                             .transaction_context
@@ -811,6 +1306,16 @@ impl LightWallet {
                     min_height,
                 );
 
+                let scan_ranges = self.scan_ranges.read().await;
+                while anchor_height < target_height
+                    && !scan_ranges.is_contiguous(
+                        BlockHeight::from_u32(anchor_height),
+                        BlockHeight::from_u32(target_height - 1),
+                    )
+                {
+                    anchor_height += 1;
+                }
+
                 Some((target_height, (target_height - anchor_height) as usize))
             }
             _ => None,
@@ -825,6 +1330,22 @@ impl LightWallet {
         }
     }
 
+    /// Scan ranges still needing work, most-recent-window first, so notes received near the
+    /// chain tip become spendable before older history finishes backfilling. See
+    /// [`ScanRanges::suggest_scan_ranges`].
+    pub async fn suggest_scan_ranges(&self) -> Vec<(BlockHeight, BlockHeight)> {
+        let tip_height = self
+            .blocks
+            .read()
+            .await
+            .first()
+            .map(|block| BlockHeight::from_u32(block.height as u32))
+            .unwrap_or_else(|| {
+                BlockHeight::from_u32(self.transaction_context.config.sapling_activation_height() as u32)
+            });
+        self.scan_ranges.read().await.suggest_scan_ranges(tip_height)
+    }
+
     pub fn memo_str(memo: Option<Memo>) -> Option<String> {
         match memo {
             Some(Memo::Text(m)) => Some(m.to_string()),
@@ -845,10 +1366,10 @@ impl LightWallet {
     async fn shielded_balance<NnMd>(
         &self,
         target_addr: Option<String>,
-        filters: &[Box<dyn Fn(&&NnMd, &TransactionMetadata) -> bool + '_>],
+        filters: &[Box<dyn Fn(&&NnMd, &TransactionMetadata) -> bool + Sync + '_>],
     ) -> u64
     where
-        NnMd: traits::NoteAndMetadata,
+        NnMd: traits::NoteAndMetadata + Sync,
     {
         let filter_notes_by_target_addr = |notedata: &&NnMd| match target_addr.as_ref() {
             Some(addr) => {
@@ -863,34 +1384,41 @@ impl LightWallet {
             }
             None => true, // If the addr is none, then get all addrs.
         };
-        self.transaction_context
-            .transaction_metadata_set
-            .read()
-            .await
-            .current
-            .values()
-            .map(|transaction| {
-                let mut filtered_notes: Box<dyn Iterator<Item = &NnMd>> = Box::new(
-                    NnMd::transaction_metadata_notes(transaction)
-                        .iter()
-                        .filter(filter_notes_by_target_addr),
-                );
-                // All filters in iterator are applied, by this loop
-                for filtering_fn in filters {
-                    filtered_notes =
-                        Box::new(filtered_notes.filter(|nnmd| filtering_fn(nnmd, transaction)))
-                }
-                filtered_notes
-                    .map(|notedata| {
-                        if notedata.spent().is_none() && notedata.unconfirmed_spent().is_none() {
-                            <NnMd as traits::NoteAndMetadata>::value(notedata)
-                        } else {
-                            0
-                        }
-                    })
-                    .sum::<u64>()
-            })
-            .sum::<u64>()
+        let per_transaction_value = |transaction: &&TransactionMetadata| {
+            let transaction: &TransactionMetadata = *transaction;
+            let mut filtered_notes: Box<dyn Iterator<Item = &NnMd>> = Box::new(
+                NnMd::transaction_metadata_notes(transaction)
+                    .iter()
+                    .filter(filter_notes_by_target_addr),
+            );
+            // All filters in iterator are applied, by this loop
+            for filtering_fn in filters {
+                filtered_notes =
+                    Box::new(filtered_notes.filter(|nnmd| filtering_fn(nnmd, transaction)))
+            }
+            filtered_notes
+                .map(|notedata| {
+                    if notedata.spent().is_none() && notedata.unconfirmed_spent().is_none() {
+                        <NnMd as traits::NoteAndMetadata>::value(notedata)
+                    } else {
+                        0
+                    }
+                })
+                .sum::<u64>()
+        };
+
+        // Collecting first lets the per-transaction note-ownership scan run across
+        // `Self::parallel_map`'s worker pool instead of serializing on a single core -- the
+        // same engine `Self::decrypt_messages` uses for its cross-product trial decryption.
+        let guard = self.transaction_context.transaction_metadata_set.read().await;
+        let transactions = guard.current.values().collect::<Vec<_>>();
+        Self::parallel_map(
+            &transactions,
+            Self::default_worker_pool_size(),
+            per_transaction_value,
+        )
+        .into_iter()
+        .sum::<u64>()
     }
 
     // Get all (unspent) utxos. Unconfirmed spent utxos are included
@@ -918,6 +1446,74 @@ impl LightWallet {
             .sum::<u64>()
     }
 
+    /// The confirmation-depth cutoff (see `AnchorDepthConfig::transparent_depth`) below which a
+    /// transparent UTXO's funding transaction counts as confirmed, clamped by `minconf` the same
+    /// way the shielded anchor depths are. `None` if the wallet hasn't scanned any blocks yet.
+    async fn transparent_confirmed_height(&self, minconf: Option<u32>) -> Option<u32> {
+        let target_height = self.get_target_height().await?;
+        let depth = AnchorDepthConfig::effective_depth(
+            self.anchor_depth.read().await.transparent_depth,
+            minconf,
+        );
+        Some(target_height.saturating_sub(depth))
+    }
+
+    /// Confirmed transparent UTXOs only, excluding ones whose funding transaction hasn't reached
+    /// `transparent_depth` confirmations yet -- including one that's still sitting in the
+    /// mempool, which is recorded at a provisional height past the chain tip. Used by
+    /// `select_notes_and_utxos`'s `transparent_only` path so a transparent-only send can't spend
+    /// funds that could still be reorged away.
+    async fn get_confirmed_utxos(&self, minconf: Option<u32>) -> Vec<Utxo> {
+        let confirmed_height = self.transparent_confirmed_height(minconf).await;
+        self.transaction_context
+            .transaction_metadata_set
+            .read()
+            .await
+            .current
+            .values()
+            .filter(|wtx| confirmed_height.map_or(false, |h| u32::from(wtx.block) <= h))
+            .flat_map(|wtx| wtx.utxos.iter())
+            .filter(|utxo| utxo.spent.is_none() && utxo.unconfirmed_spent.is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Confirmed and unconfirmed transparent value, broken down per-address. A UTXO counts as
+    /// unconfirmed if its funding transaction hasn't reached `transparent_depth` confirmations
+    /// yet, including one still sitting in the mempool; `by_address` only totals the confirmed,
+    /// spendable value, matching what a caller could actually send from that address right now.
+    pub async fn transparent_balance(&self, target_addr: Option<String>) -> TransparentBalance {
+        let confirmed_height = self.transparent_confirmed_height(None).await;
+        let mut balance = TransparentBalance::default();
+        for wtx in self
+            .transaction_context
+            .transaction_metadata_set
+            .read()
+            .await
+            .current
+            .values()
+        {
+            let confirmed = confirmed_height.map_or(false, |h| u32::from(wtx.block) <= h);
+            for utxo in wtx.utxos.iter() {
+                if utxo.spent.is_some() || utxo.unconfirmed_spent.is_some() {
+                    continue;
+                }
+                if let Some(addr) = target_addr.as_ref() {
+                    if *addr != utxo.address {
+                        continue;
+                    }
+                }
+                if confirmed {
+                    balance.confirmed += utxo.value;
+                    *balance.by_address.entry(utxo.address.clone()).or_insert(0) += utxo.value;
+                } else {
+                    balance.unconfirmed += utxo.value;
+                }
+            }
+        }
+        balance
+    }
+
     /// The following functions use a filter/map functional approach to
     /// expressively unpack different kinds of transaction data.
     pub async fn unverified_sapling_balance(&self, target_addr: Option<String>) -> u64 {
@@ -925,7 +1521,7 @@ impl LightWallet {
 
         let keys = self.transaction_context.keys.read().await;
 
-        let filters: &[Box<dyn Fn(&&SaplingNoteAndMetadata, &TransactionMetadata) -> bool>] = &[
+        let filters: &[Box<dyn Fn(&&SaplingNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] = &[
             Box::new(|notedata: &&SaplingNoteAndMetadata, _| {
                 // Check to see if we have this note's spending key.
                 keys.have_sapling_spending_key(&notedata.extfvk)
@@ -942,7 +1538,7 @@ impl LightWallet {
 
         let keys = self.transaction_context.keys.read().await;
 
-        let filters: &[Box<dyn Fn(&&OrchardNoteAndMetadata, &TransactionMetadata) -> bool>] = &[
+        let filters: &[Box<dyn Fn(&&OrchardNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] = &[
             Box::new(|notedata, _| {
                 // Check to see if we have this note's spending key.
                 keys.have_orchard_spending_key(&notedata.fvk.to_ivk(orchard::keys::Scope::External))
@@ -964,9 +1560,9 @@ impl LightWallet {
             .await
     }
 
-    async fn verified_balance<NnMd: NoteAndMetadata>(&self, target_addr: Option<String>) -> u64 {
+    async fn verified_balance<NnMd: NoteAndMetadata + Sync>(&self, target_addr: Option<String>) -> u64 {
         let anchor_height = self.get_anchor_height().await;
-        let filters: &[Box<dyn Fn(&&NnMd, &TransactionMetadata) -> bool>] =
+        let filters: &[Box<dyn Fn(&&NnMd, &TransactionMetadata) -> bool + Sync>] =
             &[Box::new(|_, transaction| {
                 transaction.block <= BlockHeight::from_u32(anchor_height)
             })];
@@ -976,9 +1572,9 @@ impl LightWallet {
     pub async fn spendable_sapling_balance(&self, target_addr: Option<String>) -> u64 {
         let anchor_height = self.get_anchor_height().await;
         let keys = self.transaction_context.keys.read().await;
-        let filters: &[Box<dyn Fn(&&SaplingNoteAndMetadata, &TransactionMetadata) -> bool>] = &[
+        let filters: &[Box<dyn Fn(&&SaplingNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] = &[
             Box::new(|_, transaction| transaction.block <= BlockHeight::from_u32(anchor_height)),
-            Box::new(|nnmd, _| {
+            Box::new(move |nnmd, _| {
                 keys.have_sapling_spending_key(&nnmd.extfvk) && nnmd.witnesses.len() > 0
             }),
         ];
@@ -988,9 +1584,9 @@ impl LightWallet {
     pub async fn spendable_orchard_balance(&self, target_addr: Option<String>) -> u64 {
         let anchor_height = self.get_anchor_height().await;
         let keys = self.transaction_context.keys.read().await;
-        let filters: &[Box<dyn Fn(&&OrchardNoteAndMetadata, &TransactionMetadata) -> bool>] = &[
+        let filters: &[Box<dyn Fn(&&OrchardNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] = &[
             Box::new(|_, transaction| transaction.block <= BlockHeight::from_u32(anchor_height)),
-            Box::new(|nnmd, _| {
+            Box::new(move |nnmd, _| {
                 keys.have_orchard_spending_key(&nnmd.fvk.to_ivk(orchard::keys::Scope::External))
                     && nnmd.witnesses.len() > 0
             }),
@@ -998,23 +1594,97 @@ impl LightWallet {
         self.shielded_balance(target_addr, filters).await
     }
 
-    pub async fn remove_unused_taddrs(&self) {
-        let taddrs = self.transaction_context.keys.read().await.get_all_taddrs();
-        if taddrs.len() <= 1 {
-            return;
-        }
+    /// Value of this wallet's own sapling notes currently tied up by an in-flight send: either
+    /// reserved by [`Self::reserve_selected_notes`] ahead of building, or already broadcast and
+    /// sitting unconfirmed in the mempool. Reported separately from `spendable_sapling_balance`
+    /// so callers can show "pending change" instead of silently shrinking the spendable total.
+    pub async fn pending_sapling_balance(&self, target_addr: Option<String>) -> u64 {
+        let keys = self.transaction_context.keys.read().await;
+        let filters: &[Box<dyn Fn(&&SaplingNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] =
+            &[Box::new(|nnmd: &&SaplingNoteAndMetadata, _| {
+                keys.have_sapling_spending_key(&nnmd.extfvk)
+            })];
+        self.pending_balance(target_addr, filters).await
+    }
 
-        let highest_account = self
-            .transaction_context
-            .transaction_metadata_set
-            .read()
-            .await
-            .current
-            .values()
-            .flat_map(|wtx| {
-                wtx.utxos.iter().map(|u| {
-                    taddrs
-                        .iter()
+    /// As [`Self::pending_sapling_balance`], for Orchard notes.
+    pub async fn pending_orchard_balance(&self, target_addr: Option<String>) -> u64 {
+        let keys = self.transaction_context.keys.read().await;
+        let filters: &[Box<dyn Fn(&&OrchardNoteAndMetadata, &TransactionMetadata) -> bool + Sync>] =
+            &[Box::new(|nnmd, _| {
+                keys.have_orchard_spending_key(&nnmd.fvk.to_ivk(orchard::keys::Scope::External))
+            })];
+        self.pending_balance(target_addr, filters).await
+    }
+
+    /// As [`Self::shielded_balance`], but sums notes with `unconfirmed_spent` set instead of
+    /// excluding them -- the complement of what `shielded_balance` counts as spendable.
+    async fn pending_balance<NnMd>(
+        &self,
+        target_addr: Option<String>,
+        filters: &[Box<dyn Fn(&&NnMd, &TransactionMetadata) -> bool + Sync + '_>],
+    ) -> u64
+    where
+        NnMd: traits::NoteAndMetadata + Sync,
+    {
+        let filter_notes_by_target_addr = |notedata: &&NnMd| match target_addr.as_ref() {
+            Some(addr) => {
+                use self::traits::Recipient as _;
+                let diversified_address = &notedata
+                    .fvk()
+                    .diversified_address(*notedata.diversifier())
+                    .unwrap();
+                *addr
+                    == diversified_address
+                        .b32encode_for_network(&self.transaction_context.config.chain)
+            }
+            None => true,
+        };
+
+        let transactions = self.transaction_context.transaction_metadata_set.read().await;
+        transactions
+            .current
+            .values()
+            .map(|transaction| {
+                let mut filtered_notes: Box<dyn Iterator<Item = &NnMd>> = Box::new(
+                    NnMd::transaction_metadata_notes(transaction)
+                        .iter()
+                        .filter(filter_notes_by_target_addr),
+                );
+                for filtering_fn in filters {
+                    filtered_notes =
+                        Box::new(filtered_notes.filter(|nnmd| filtering_fn(nnmd, transaction)))
+                }
+                filtered_notes
+                    .map(|notedata| {
+                        if notedata.spent().is_none() && notedata.unconfirmed_spent().is_some() {
+                            <NnMd as traits::NoteAndMetadata>::value(notedata)
+                        } else {
+                            0
+                        }
+                    })
+                    .sum::<u64>()
+            })
+            .sum::<u64>()
+    }
+
+    pub async fn remove_unused_taddrs(&self) {
+        let taddrs = self.transaction_context.keys.read().await.get_all_taddrs();
+        if taddrs.len() <= 1 {
+            return;
+        }
+
+        let highest_account = self
+            .transaction_context
+            .transaction_metadata_set
+            .read()
+            .await
+            .current
+            .values()
+            .flat_map(|wtx| {
+                wtx.utxos.iter().map(|u| {
+                    taddrs
+                        .iter()
                         .position(|taddr| *taddr == u.address)
                         .unwrap_or(taddrs.len())
                 })
@@ -1085,8 +1755,17 @@ impl LightWallet {
     }
 
     pub async fn decrypt_message(&self, enc: Vec<u8>) -> Option<Message> {
-        // Collect all the ivks in the wallet
-        let ivks: Vec<_> = self
+        self.decrypt_messages(vec![enc]).await.swap_remove(0)
+    }
+
+    /// Trial-decrypts every ciphertext in `enc` against every sapling ivk in the wallet, one
+    /// ciphertext per output slot, returning the first matching [`Message`] (or `None`).
+    ///
+    /// With more than one ivk and more than one ciphertext, the cross product is split across
+    /// [`Self::default_worker_pool_size`] worker threads via [`Self::parallel_map`] -- the same
+    /// engine [`Self::shielded_balance`] uses for its per-transaction note-ownership scan.
+    pub async fn decrypt_messages(&self, enc: Vec<Vec<u8>>) -> Vec<Option<Message>> {
+        let ivks: Vec<sapling::SaplingIvk> = self
             .transaction_context
             .keys
             .read()
@@ -1096,16 +1775,60 @@ impl LightWallet {
             .map(|extfvk| extfvk.fvk.vk.ivk())
             .collect();
 
-        // Attempt decryption with all available ivks, one at a time. This is pretty fast, so need need for fancy multithreading
-        for ivk in ivks {
-            if let Ok(msg) = Message::decrypt(&enc, &ivk) {
-                // If decryption succeeded for this IVK, return the decrypted memo and the matched address
-                return Some(msg);
-            }
+        Self::trial_decrypt_batch(&enc, &ivks, Self::default_worker_pool_size())
+    }
+
+    /// Number of worker threads [`Self::parallel_map`] splits work across by default. Exposed as
+    /// a separate `pool_size` parameter on the `_batch`/`parallel_map` helpers rather than a
+    /// fixed constant, so callers (and tests) can override it; defaults to the available cores.
+    fn default_worker_pool_size() -> usize {
+        std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    }
+
+    /// Trial-decrypts each ciphertext in `enc` against `ivks`, keeping the first ivk that
+    /// succeeds per ciphertext. Falls back to the serial one-ivk-at-a-time loop when there's
+    /// only one ivk or one ciphertext: decryption is already fast per attempt, and threading
+    /// only pays off once there's an actual cross product of work to spread out.
+    fn trial_decrypt_batch(
+        enc: &[Vec<u8>],
+        ivks: &[sapling::SaplingIvk],
+        pool_size: usize,
+    ) -> Vec<Option<Message>> {
+        let decrypt_one =
+            |ciphertext: &Vec<u8>| ivks.iter().find_map(|ivk| Message::decrypt(ciphertext, ivk).ok());
+
+        if ivks.len() <= 1 || enc.len() <= 1 {
+            return enc.iter().map(decrypt_one).collect();
         }
 
-        // If nothing matched
-        None
+        Self::parallel_map(enc, pool_size, decrypt_one)
+    }
+
+    /// Splits `items` across up to `pool_size` worker threads and maps each one with `f`,
+    /// collecting results in the original order. Falls back to a plain serial map when there's
+    /// only one item or `pool_size <= 1`, since spinning up threads isn't worth it for a single
+    /// unit of work.
+    fn parallel_map<T, R>(items: &[T], pool_size: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R>
+    where
+        T: Sync,
+        R: Send,
+    {
+        if pool_size <= 1 || items.len() <= 1 {
+            return items.iter().map(|item| f(item)).collect();
+        }
+
+        let chunk_size = (items.len() + pool_size - 1) / pool_size;
+        std::thread::scope(|scope| {
+            items
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(|| chunk.iter().map(|item| f(item)).collect::<Vec<R>>()))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
     }
 
     // Add the spent_at_height for each sapling note that has been spent. This field was added in wallet version 8,
@@ -1168,20 +1891,25 @@ impl LightWallet {
         transparent_only: bool,
         shield_transparenent: bool,
         prefer_orchard_over_sapling: bool,
+        note_selection_strategy: NoteSelectionStrategy,
+        minconf: Option<u32>,
     ) -> (
         Vec<SpendableOrchardNote>,
         Vec<SpendableSaplingNote>,
         Vec<Utxo>,
         Amount,
     ) {
-        // First, if we are allowed to pick transparent value, pick them all
+        let anchor_depth = *self.anchor_depth.read().await;
+        let sapling_depth =
+            AnchorDepthConfig::effective_depth(anchor_depth.sapling_depth, minconf);
+        let orchard_depth =
+            AnchorDepthConfig::effective_depth(anchor_depth.orchard_depth, minconf);
+
+        // First, if we are allowed to pick transparent value, pick them all. Both a
+        // transparent-only send and auto-shielding's fold-in draw only from confirmed UTXOs,
+        // same as the shielded pools do.
         let utxos = if transparent_only || shield_transparenent {
-            self.get_utxos()
-                .await
-                .iter()
-                .filter(|utxo| utxo.unconfirmed_spent.is_none() && utxo.spent.is_none())
-                .map(|utxo| utxo.clone())
-                .collect::<Vec<_>>()
+            self.get_confirmed_utxos(minconf).await
         } else {
             vec![]
         };
@@ -1201,12 +1929,15 @@ impl LightWallet {
         // Select the minimum number of notes required to satisfy the target value
         if prefer_orchard_over_sapling {
             let sapling_candidates = self
-                .get_all_domain_specific_notes::<SaplingDomain<zingoconfig::Network>>()
+                .get_all_domain_specific_notes::<SaplingDomain<zingoconfig::Network>>(
+                    sapling_depth,
+                )
                 .await;
             (sapling_notes, sapling_value_selected) =
                 Self::add_notes_to_total::<SaplingDomain<zingoconfig::Network>>(
                     sapling_candidates,
                     (target_amount - transparent_value_selected).unwrap(),
+                    note_selection_strategy,
                 );
             if transparent_value_selected + sapling_value_selected >= Some(target_amount) {
                 return (
@@ -1217,10 +1948,13 @@ impl LightWallet {
                 );
             }
         }
-        let orchard_candidates = self.get_all_domain_specific_notes::<OrchardDomain>().await;
+        let orchard_candidates = self
+            .get_all_domain_specific_notes::<OrchardDomain>(orchard_depth)
+            .await;
         let (orchard_notes, orchard_value_selected) = Self::add_notes_to_total::<OrchardDomain>(
             orchard_candidates,
             (target_amount - transparent_value_selected - sapling_value_selected).unwrap(),
+            note_selection_strategy,
         );
         if transparent_value_selected + sapling_value_selected + orchard_value_selected
             >= Some(target_amount)
@@ -1235,12 +1969,15 @@ impl LightWallet {
         }
         if !prefer_orchard_over_sapling {
             let sapling_candidates = self
-                .get_all_domain_specific_notes::<SaplingDomain<zingoconfig::Network>>()
+                .get_all_domain_specific_notes::<SaplingDomain<zingoconfig::Network>>(
+                    sapling_depth,
+                )
                 .await;
             (sapling_notes, sapling_value_selected) =
                 Self::add_notes_to_total::<SaplingDomain<zingoconfig::Network>>(
                     sapling_candidates,
                     (target_amount - transparent_value_selected).unwrap(),
+                    note_selection_strategy,
                 );
             if transparent_value_selected + sapling_value_selected + orchard_value_selected
                 >= Some(target_amount)
@@ -1259,7 +1996,15 @@ impl LightWallet {
         (vec![], vec![], vec![], Amount::zero())
     }
 
-    async fn get_all_domain_specific_notes<D>(&self) -> Vec<Vec<D::SpendableNote>>
+    /// Collects every spendable note for pool `D`, witnessed against the anchor block that has
+    /// `anchor_depth` confirmations (the chain tip for a depth of 0 or 1). Returns a
+    /// single-element outer `Vec` (rather than one candidate set per entry of the
+    /// `anchor_offset` fallback array, as note selection used to build) since
+    /// [`Self::select_notes_and_utxos`] has already resolved the one depth to use.
+    async fn get_all_domain_specific_notes<D>(
+        &self,
+        anchor_depth: u32,
+    ) -> Vec<Vec<D::SpendableNote>>
     where
         D: DomainWalletExt<zingoconfig::Network>,
         <D as Domain>::Recipient: traits::Recipient,
@@ -1269,47 +2014,40 @@ impl LightWallet {
         let keys = keys_arc.read().await;
         let notes_arc = self.transactions();
         let notes = notes_arc.read().await;
-        self.transaction_context
-            .config
-            .anchor_offset
+        let mut candidate_notes = notes
+            .current
             .iter()
-            .map(|anchor_offset| {
-                let mut candidate_notes = notes
-                    .current
+            .flat_map(|(transaction_id, transaction)| {
+                D::WalletNote::transaction_metadata_notes(transaction)
                     .iter()
-                    .flat_map(|(transaction_id, transaction)| {
-                        D::WalletNote::transaction_metadata_notes(transaction)
-                            .iter()
-                            .map(move |note| (*transaction_id, note))
-                    })
-                    .filter(|(_, note)| note.value() > 0)
-                    .filter_map(|(transaction_id, note)| {
-                        // Filter out notes that are already spent
-                        if note.spent().is_some() || note.unconfirmed_spent().is_some() {
-                            None
-                        } else {
-                            // Get the spending key for the selected fvk, if we have it
-                            let extsk = keys.get_sk_for_fvk::<D>(&note.fvk());
-                            SpendableNote::from(
-                                transaction_id,
-                                note,
-                                *anchor_offset as usize,
-                                &extsk,
-                            )
-                        }
-                    })
-                    .collect::<Vec<D::SpendableNote>>();
-                candidate_notes.sort_by_key(|spendable_note| {
-                    D::WalletNote::value_from_note(&spendable_note.note())
-                });
-                candidate_notes
+                    .map(move |note| (*transaction_id, note))
             })
-            .collect()
+            .filter(|(_, note)| note.value() > 0)
+            .filter_map(|(transaction_id, note)| {
+                // Filter out notes that are already spent
+                if note.spent().is_some() || note.unconfirmed_spent().is_some() {
+                    None
+                } else {
+                    // Get the spending key for the selected fvk, if we have it
+                    let extsk = keys.get_sk_for_fvk::<D>(&note.fvk());
+                    SpendableNote::from(
+                        transaction_id,
+                        note,
+                        anchor_depth.saturating_sub(1) as usize,
+                        &extsk,
+                    )
+                }
+            })
+            .collect::<Vec<D::SpendableNote>>();
+        candidate_notes
+            .sort_by_key(|spendable_note| D::WalletNote::value_from_note(&spendable_note.note()));
+        vec![candidate_notes]
     }
 
     fn add_notes_to_total<D: DomainWalletExt<zingoconfig::Network>>(
         candidates: Vec<Vec<D::SpendableNote>>,
         target_amount: Amount,
+        note_selection_strategy: NoteSelectionStrategy,
     ) -> (Vec<D::SpendableNote>, Amount)
     where
         D::Note: PartialEq + Clone,
@@ -1317,44 +2055,216 @@ impl LightWallet {
     {
         let mut notes = vec![];
         let mut value_selected = Amount::zero();
-        let mut candidates = candidates.into_iter();
-        loop {
-            if let Some(candidate_set) = candidates.next() {
-                notes = candidate_set
-                    .into_iter()
-                    .scan(Amount::zero(), |running_total, spendable| {
-                        if *running_total >= target_amount {
-                            None
-                        } else {
-                            *running_total +=
-                                Amount::from_u64(D::WalletNote::value_from_note(&spendable.note()))
-                                    .unwrap();
-                            Some(spendable)
-                        }
+        for candidate_set in candidates {
+            (notes, value_selected) = match note_selection_strategy {
+                NoteSelectionStrategy::BranchAndBound { cost_of_change } => {
+                    Self::branch_and_bound_select::<D>(
+                        &candidate_set,
+                        target_amount,
+                        cost_of_change,
+                    )
+                    .unwrap_or_else(|| {
+                        Self::greedy_ascending_select::<D>(candidate_set, target_amount)
                     })
-                    .collect::<Vec<_>>();
-                value_selected = notes.iter().fold(Amount::zero(), |prev, sn| {
-                    (prev + Amount::from_u64(D::WalletNote::value_from_note(&sn.note())).unwrap())
-                        .unwrap()
-                });
-
-                if value_selected >= target_amount {
-                    break (notes, value_selected);
                 }
-            } else {
-                break (notes, value_selected);
+                NoteSelectionStrategy::GreedyAscending => {
+                    Self::greedy_ascending_select::<D>(candidate_set, target_amount)
+                }
+            };
+
+            if value_selected >= target_amount {
+                break;
             }
         }
+        (notes, value_selected)
     }
 
+    /// The classic "keep adding the smallest unspent note until we have enough" selector. Tends
+    /// to pick many small notes, which means bigger transactions (more proofs) and more linkage
+    /// between notes. Used as the fallback when branch-and-bound can't find a subset landing
+    /// close to the target.
+    fn greedy_ascending_select<D: DomainWalletExt<zingoconfig::Network>>(
+        candidate_set: Vec<D::SpendableNote>,
+        target_amount: Amount,
+    ) -> (Vec<D::SpendableNote>, Amount)
+    where
+        D::Note: PartialEq + Clone,
+        D::Recipient: traits::Recipient,
+    {
+        let notes = candidate_set
+            .into_iter()
+            .scan(Amount::zero(), |running_total, spendable| {
+                if *running_total >= target_amount {
+                    None
+                } else {
+                    *running_total +=
+                        Amount::from_u64(D::WalletNote::value_from_note(&spendable.note()))
+                            .unwrap();
+                    Some(spendable)
+                }
+            })
+            .collect::<Vec<_>>();
+        let value_selected = notes.iter().fold(Amount::zero(), |prev, sn| {
+            (prev + Amount::from_u64(D::WalletNote::value_from_note(&sn.note())).unwrap()).unwrap()
+        });
+        (notes, value_selected)
+    }
+
+    /// Searches `candidate_set` (sorted descending by value) depth-first for a subset whose total
+    /// falls within `[target_amount, target_amount + cost_of_change]`, minimizing the number of
+    /// notes selected. `cost_of_change` is the marginal fee of adding a change output: landing in
+    /// this window means the transaction either needs no change output at all, or only a cheap
+    /// one, instead of accumulating many small notes and a large one.
+    ///
+    /// Returns `None` if no such subset exists (or the candidate set is too large to search
+    /// exhaustively), in which case the caller falls back to [`Self::greedy_ascending_select`].
+    fn branch_and_bound_select<D: DomainWalletExt<zingoconfig::Network>>(
+        candidate_set: &[D::SpendableNote],
+        target_amount: Amount,
+        cost_of_change: u64,
+    ) -> Option<(Vec<D::SpendableNote>, Amount)>
+    where
+        D::Note: PartialEq + Clone,
+        D::Recipient: traits::Recipient,
+    {
+        // Bitcoin Core's BnB bounds the search the same way: beyond a few dozen UTXOs the
+        // include/exclude tree is too large to search exhaustively in a reasonable time, so we
+        // bail out to the greedy fallback instead.
+        const MAX_BRANCH_AND_BOUND_CANDIDATES: usize = 32;
+        if candidate_set.is_empty() || candidate_set.len() > MAX_BRANCH_AND_BOUND_CANDIDATES {
+            return None;
+        }
+
+        let target = u64::from(target_amount);
+        let window_high = target.checked_add(cost_of_change)?;
+
+        let mut indices: Vec<usize> = (0..candidate_set.len()).collect();
+        indices.sort_by_key(|&i| {
+            cmp::Reverse(D::WalletNote::value_from_note(&candidate_set[i].note()))
+        });
+        let values: Vec<u64> = indices
+            .iter()
+            .map(|&i| D::WalletNote::value_from_note(&candidate_set[i].note()))
+            .collect();
+
+        let mut suffix_total = vec![0u64; values.len() + 1];
+        for i in (0..values.len()).rev() {
+            suffix_total[i] = suffix_total[i + 1] + values[i];
+        }
+
+        const MAX_BRANCH_AND_BOUND_TRIES: u32 = 100_000;
+        let mut selected = Vec::new();
+        let mut best: Option<Vec<usize>> = None;
+        let mut tries = MAX_BRANCH_AND_BOUND_TRIES;
+        branch_and_bound_search(
+            &values,
+            &suffix_total,
+            0,
+            0,
+            &mut selected,
+            target,
+            window_high,
+            &mut best,
+            &mut tries,
+        );
+
+        best.map(|best_positions| {
+            let selected_indices: Vec<usize> =
+                best_positions.iter().map(|&pos| indices[pos]).collect();
+            let mut selected_indices_sorted = selected_indices.clone();
+            selected_indices_sorted.sort_unstable();
+            let notes: Vec<D::SpendableNote> = candidate_set
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| selected_indices_sorted.contains(i))
+                .map(|(_, note)| note.clone())
+                .collect();
+            let value_selected = notes.iter().fold(Amount::zero(), |prev, sn| {
+                (prev + Amount::from_u64(D::WalletNote::value_from_note(&sn.note())).unwrap())
+                    .unwrap()
+            });
+            (notes, value_selected)
+        })
+    }
+
+    /// `auto_shield_transparent` opportunistically folds the wallet's confirmed transparent
+    /// UTXOs into this transaction's inputs whenever at least one recipient is shielded or
+    /// unified, sending the surplus back to the wallet as shielded change -- so a shielded send
+    /// quietly shields any transparent funds lying around, instead of leaking transparent
+    /// activity across a separate transaction later. It has no effect when every recipient is
+    /// transparent, since there would be nothing shielded to fold the change into.
     pub async fn send_to_address<F, Fut, P: TxProver>(
         &self,
         prover: P,
         transparent_only: bool,
         migrate_sapling_to_orchard: bool,
+        auto_shield_transparent: bool,
         tos: Vec<(&str, u64, Option<String>)>,
         broadcast_fn: F,
-    ) -> Result<(String, Vec<u8>), String>
+    ) -> Result<(String, Vec<u8>, u64), String>
+    where
+        F: Fn(Box<[u8]>) -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        self.send_to_address_with_minconf(
+            prover,
+            transparent_only,
+            migrate_sapling_to_orchard,
+            auto_shield_transparent,
+            tos,
+            broadcast_fn,
+            None,
+        )
+        .await
+    }
+
+    /// As [`Self::send_to_address`], but takes a single ZIP 321 payment URI (or a bare address,
+    /// as a one-payment shorthand) instead of a pre-built `tos` list.
+    pub async fn send_to_address_uri<F, Fut, P: TxProver>(
+        &self,
+        prover: P,
+        transparent_only: bool,
+        migrate_sapling_to_orchard: bool,
+        auto_shield_transparent: bool,
+        uri: &str,
+        broadcast_fn: F,
+    ) -> Result<(String, Vec<u8>, u64), String>
+    where
+        F: Fn(Box<[u8]>) -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let payments =
+            zip321::parse_payment_uri(&self.transaction_context.config.chain, uri)?;
+        let tos = payments
+            .iter()
+            .map(|(address, amount, memo)| (address.as_str(), *amount, memo.clone()))
+            .collect();
+
+        self.send_to_address(
+            prover,
+            transparent_only,
+            migrate_sapling_to_orchard,
+            auto_shield_transparent,
+            tos,
+            broadcast_fn,
+        )
+        .await
+    }
+
+    /// As [`Self::send_to_address`], but lets the caller require fewer than the configured
+    /// [`AnchorDepthConfig`] confirmations on the notes it spends (e.g. to spend change from a
+    /// transaction the caller already knows is safely mined, without waiting out the default
+    /// anchor depth).
+    pub async fn send_to_address_with_minconf<F, Fut, P: TxProver>(
+        &self,
+        prover: P,
+        transparent_only: bool,
+        migrate_sapling_to_orchard: bool,
+        auto_shield_transparent: bool,
+        tos: Vec<(&str, u64, Option<String>)>,
+        broadcast_fn: F,
+        minconf: Option<u32>,
+    ) -> Result<(String, Vec<u8>, u64), String>
     where
         F: Fn(Box<[u8]>) -> Fut,
         Fut: Future<Output = Result<String, String>>,
@@ -1368,14 +2278,16 @@ impl LightWallet {
                 prover,
                 transparent_only,
                 migrate_sapling_to_orchard,
+                auto_shield_transparent,
                 tos,
                 broadcast_fn,
+                minconf,
             )
             .await
         {
-            Ok((transaction_id, raw_transaction)) => {
+            Ok((transaction_id, raw_transaction, fee)) => {
                 self.set_send_success(transaction_id.clone()).await;
-                Ok((transaction_id, raw_transaction))
+                Ok((transaction_id, raw_transaction, fee))
             }
             Err(e) => {
                 self.set_send_error(format!("{}", e)).await;
@@ -1384,14 +2296,58 @@ impl LightWallet {
         }
     }
 
+    /// Sweeps every spendable transparent UTXO into the wallet's own Sapling address, paying
+    /// the ZIP 317 conventional fee out of the swept value. Only confirmed UTXOs are swept. This is the standalone counterpart
+    /// to `send_to_address`'s `auto_shield_transparent`: where that flag shields transparent
+    /// funds for free alongside an outgoing send, this clears out all transparent activity
+    /// up-front, for wallets that don't have a shielded send of their own to piggyback on.
+    pub async fn shield_all_utxos<F, Fut, P: TxProver>(
+        &self,
+        prover: P,
+        broadcast_fn: F,
+    ) -> Result<(String, Vec<u8>, u64), String>
+    where
+        F: Fn(Box<[u8]>) -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let utxos = self.get_confirmed_utxos(None).await;
+        let spendable_transparent_value: u64 = utxos.iter().map(|utxo| utxo.value).sum();
+
+        // The conventional fee for spending every UTXO into one Sapling output, the same shape
+        // `send_to_address` then recomputes the fee from, so the swept value alone covers it.
+        let estimated_fee = u64::from(conventional_fee(utxos.len(), 0, 0, 1, 0, 0));
+        if spendable_transparent_value <= estimated_fee {
+            return Err(
+                "Not enough spendable transparent funds to cover the shielding fee".to_string(),
+            );
+        }
+
+        let own_sapling_address = self.keys().read().await.zkeys[0].zaddress.clone();
+        self.send_to_address(
+            prover,
+            false,
+            false,
+            true,
+            vec![(
+                own_sapling_address.as_str(),
+                spendable_transparent_value - estimated_fee,
+                None,
+            )],
+            broadcast_fn,
+        )
+        .await
+    }
+
     async fn send_to_address_internal<F, Fut, P: TxProver>(
         &self,
         prover: P,
         transparent_only: bool,
         migrate_sapling_to_orchard: bool,
+        auto_shield_transparent: bool,
         tos: Vec<(&str, u64, Option<String>)>,
         broadcast_fn: F,
-    ) -> Result<(String, Vec<u8>), String>
+        minconf: Option<u32>,
+    ) -> Result<(String, Vec<u8>, u64), String>
     where
         F: Fn(Box<[u8]>) -> Fut,
         Fut: Future<Output = Result<String, String>>,
@@ -1400,6 +2356,164 @@ impl LightWallet {
             return Err("Cannot spend while wallet is locked".to_string());
         }
 
+        let plan = self
+            .build_transaction_plan(
+                transparent_only,
+                migrate_sapling_to_orchard,
+                auto_shield_transparent,
+                tos,
+                minconf,
+            )
+            .await?;
+
+        self.build_and_broadcast_plan(plan, prover, broadcast_fn)
+            .await
+    }
+
+    /// A txid that will never be a real, broadcastable transaction, used to mark notes/utxos
+    /// `unconfirmed_spent` the moment they're selected, before the transaction that will
+    /// actually spend them has been built (and so doesn't have a real txid yet). This closes
+    /// the window where two concurrent `send_to_address` calls could otherwise both select the
+    /// same note: the second caller's `select_notes_and_utxos` already excludes anything with
+    /// `unconfirmed_spent` set, same as it does for a note spent by a fully broadcast
+    /// transaction. [`Self::build_and_broadcast_plan`] overwrites this placeholder with the
+    /// real txid once one exists, and releases the reservation if building or broadcasting
+    /// fails before that happens.
+    fn next_reservation_txid(&self) -> TxId {
+        let counter = self
+            .reservation_counter
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&counter.to_le_bytes());
+        TxId::from_bytes(bytes)
+    }
+
+    /// Marks `sapling_notes`, `orchard_notes`, and `utxos` as spent by `reservation_txid`, so
+    /// they're excluded from selection until [`Self::release_reserved_notes`] clears the
+    /// reservation or a real broadcast overwrites it.
+    async fn reserve_selected_notes(
+        &self,
+        sapling_notes: &[SpendableSaplingNote],
+        orchard_notes: &[SpendableOrchardNote],
+        utxos: &[Utxo],
+        reservation_txid: TxId,
+        target_height: BlockHeight,
+    ) {
+        let mut transactions = self.transaction_context.transaction_metadata_set.write().await;
+        for selected in sapling_notes {
+            if let Some(nd) = transactions
+                .current
+                .get_mut(&selected.transaction_id)
+                .and_then(|wtx| {
+                    wtx.sapling_notes
+                        .iter_mut()
+                        .find(|nd| nd.nullifier == selected.nullifier)
+                })
+            {
+                nd.unconfirmed_spent = Some((reservation_txid, u32::from(target_height)));
+            }
+        }
+        for selected in orchard_notes {
+            if let Some(nd) = transactions
+                .current
+                .get_mut(&selected.transaction_id)
+                .and_then(|wtx| {
+                    wtx.orchard_notes
+                        .iter_mut()
+                        .find(|nd| nd.nullifier == selected.nullifier)
+                })
+            {
+                nd.unconfirmed_spent = Some((reservation_txid, u32::from(target_height)));
+            }
+        }
+        for utxo in utxos {
+            if let Some(spent_utxo) = transactions
+                .current
+                .get_mut(&utxo.txid)
+                .and_then(|wtx| {
+                    wtx.utxos
+                        .iter_mut()
+                        .find(|u| utxo.txid == u.txid && utxo.output_index == u.output_index)
+                })
+            {
+                spent_utxo.unconfirmed_spent = Some((reservation_txid, u32::from(target_height)));
+            }
+        }
+    }
+
+    /// Releases a reservation made by [`Self::reserve_selected_notes`] after building or
+    /// broadcasting the transaction that was going to spend it fails. Only clears
+    /// `unconfirmed_spent` entries that still carry `reservation_txid`, so this can't clobber
+    /// a later, unrelated spend of the same note.
+    async fn release_reserved_notes(
+        &self,
+        sapling_notes: &[SpendableSaplingNote],
+        orchard_notes: &[SpendableOrchardNote],
+        utxos: &[Utxo],
+        reservation_txid: TxId,
+    ) {
+        let is_ours = |spent: &Option<(TxId, u32)>| {
+            matches!(spent, Some((txid, _)) if *txid == reservation_txid)
+        };
+        let mut transactions = self.transaction_context.transaction_metadata_set.write().await;
+        for selected in sapling_notes {
+            if let Some(nd) = transactions
+                .current
+                .get_mut(&selected.transaction_id)
+                .and_then(|wtx| {
+                    wtx.sapling_notes
+                        .iter_mut()
+                        .find(|nd| nd.nullifier == selected.nullifier)
+                })
+            {
+                if is_ours(&nd.unconfirmed_spent) {
+                    nd.unconfirmed_spent = None;
+                }
+            }
+        }
+        for selected in orchard_notes {
+            if let Some(nd) = transactions
+                .current
+                .get_mut(&selected.transaction_id)
+                .and_then(|wtx| {
+                    wtx.orchard_notes
+                        .iter_mut()
+                        .find(|nd| nd.nullifier == selected.nullifier)
+                })
+            {
+                if is_ours(&nd.unconfirmed_spent) {
+                    nd.unconfirmed_spent = None;
+                }
+            }
+        }
+        for utxo in utxos {
+            if let Some(spent_utxo) = transactions
+                .current
+                .get_mut(&utxo.txid)
+                .and_then(|wtx| {
+                    wtx.utxos
+                        .iter_mut()
+                        .find(|u| utxo.txid == u.txid && utxo.output_index == u.output_index)
+                })
+            {
+                if is_ours(&spent_utxo.unconfirmed_spent) {
+                    spent_utxo.unconfirmed_spent = None;
+                }
+            }
+        }
+    }
+
+    /// Resolves note/UTXO selection, anchors, and outputs (including change) into a
+    /// standalone [`TransactionPlan`], without touching the prover. See the type's docs
+    /// for why this is kept separate from building.
+    async fn build_transaction_plan(
+        &self,
+        transparent_only: bool,
+        migrate_sapling_to_orchard: bool,
+        auto_shield_transparent: bool,
+        tos: Vec<(&str, u64, Option<String>)>,
+        minconf: Option<u32>,
+    ) -> Result<TransactionPlan, String> {
         let start_time = now();
         if tos.len() == 0 {
             return Err("Need at least one destination address".to_string());
@@ -1435,42 +2549,116 @@ impl LightWallet {
             .collect::<Result<Vec<(address::RecipientAddress, Amount, Option<String>)>, String>>(
             )?;
 
+        // Only worth folding transparent UTXOs into the inputs (and their surplus into shielded
+        // change) if there's a shielded output to carry that change; otherwise there's nothing
+        // to shield the surplus into and we fall back to spending only what's needed.
+        let has_shielded_recipient = recepients
+            .iter()
+            .any(|(to, _, _)| !matches!(to, address::RecipientAddress::Transparent(_)));
+        let shield_transparent_utxos = auto_shield_transparent && has_shielded_recipient;
+
+        // Outputs are fixed up front, so we can count how many logical actions they cost
+        // in each pool once, outside the fee/selection loop below.
+        let transparent_output_count = recepients
+            .iter()
+            .filter(|(to, _, _)| matches!(to, address::RecipientAddress::Transparent(_)))
+            .count();
+        let orchard_output_count = recepients
+            .iter()
+            .filter(|(to, _, _)| match to {
+                address::RecipientAddress::Unified(ua) => ua.orchard().is_some(),
+                _ => false,
+            })
+            .count();
+        let sapling_output_count = recepients.len() - transparent_output_count - orchard_output_count;
+
         // Select notes to cover the target value
         println!("{}: Selecting notes", now() - start_time);
 
-        let target_amount = (Amount::from_u64(total_value).unwrap() + DEFAULT_FEE).unwrap();
         let target_height = match self.get_target_height().await {
             Some(h) => BlockHeight::from_u32(h),
             None => return Err("No blocks in wallet to target, please sync first".to_string()),
         };
 
-        // Create a map from address -> sk for all taddrs, so we can spend from the
-        // right address
-        let address_to_sk = self
-            .transaction_context
-            .keys
-            .read()
-            .await
-            .get_taddr_to_sk_map();
+        // Pick notes against a fee estimate, then recompute the ZIP 317-style conventional
+        // fee from the shape of what was actually selected; if that fee is higher than our
+        // guess, the target grows and we need to select again. Converges in a handful of
+        // iterations in practice since adding notes can only ever push the fee up, never down.
+        let mut fee = DEFAULT_FEE;
+        let mut selection = None;
+        for _ in 0..MAX_FEE_ESTIMATION_ITERATIONS {
+            let target_amount = (Amount::from_u64(total_value).unwrap() + fee).unwrap();
+            let (orchard_notes, sapling_notes, utxos, selected_value) = self
+                .select_notes_and_utxos(
+                    target_amount,
+                    transparent_only,
+                    shield_transparent_utxos,
+                    migrate_sapling_to_orchard,
+                    NoteSelectionStrategy::BranchAndBound {
+                        cost_of_change: u64::from(fee),
+                    },
+                    minconf,
+                )
+                .await;
+            if selected_value < target_amount {
+                let configured_depth = *self.anchor_depth.read().await;
+                let required_depth = AnchorDepthConfig::effective_depth(
+                    cmp::max(configured_depth.orchard_depth, configured_depth.sapling_depth),
+                    minconf,
+                );
+                let e = format!(
+                    "Insufficient verified funds. Have {} zats, need {} zats. NOTE: funds need at least {} confirmations before they can be spent.",
+                    u64::from(selected_value), u64::from(target_amount), required_depth.max(1)
+                );
+                error!("{}", e);
+                return Err(e);
+            }
+
+            let recomputed_fee = conventional_fee(
+                utxos.len(),
+                transparent_output_count,
+                sapling_notes.len(),
+                sapling_output_count,
+                orchard_notes.len(),
+                orchard_output_count,
+            );
+            if recomputed_fee == fee {
+                selection = Some((orchard_notes, sapling_notes, utxos, selected_value));
+                break;
+            }
+            fee = recomputed_fee;
+        }
+
+        let (orchard_notes, sapling_notes, utxos, selected_value) = selection.ok_or_else(|| {
+            "Could not converge on a conventional fee for this transaction".to_string()
+        })?;
+        println!(
+            "Selected notes worth {}, paying a fee of {}",
+            u64::from(selected_value),
+            u64::from(fee)
+        );
 
-        let (orchard_notes, sapling_notes, utxos, selected_value) = self
-            .select_notes_and_utxos(
-                target_amount,
-                transparent_only,
-                true,
-                migrate_sapling_to_orchard,
-            )
-            .await;
-        if selected_value < target_amount {
-            let e = format!(
-                "Insufficient verified funds. Have {} zats, need {} zats. NOTE: funds need at least {} confirmations before they can be spent.",
-                u64::from(selected_value), u64::from(target_amount), self.transaction_context.config
-                .anchor_offset.last().unwrap() + 1
+        let touched_pools = touched_pools(&orchard_notes, &sapling_notes, &utxos);
+        if touched_pools.len() > 1 {
+            warn!(
+                "Spend draws from multiple pools ({:?}); this links them together on-chain, \
+                 reducing privacy beyond what this transaction's outputs already reveal",
+                touched_pools
             );
-            error!("{}", e);
-            return Err(e);
         }
-        println!("Selected notes worth {}", u64::from(selected_value));
+
+        // Reserve the selected notes/utxos immediately, before building or broadcasting, so a
+        // concurrent `send_to_address` call can't pick the same ones out from under us. See
+        // `reserve_selected_notes` for why this uses a placeholder txid.
+        let reservation_txid = self.next_reservation_txid();
+        self.reserve_selected_notes(
+            &sapling_notes,
+            &orchard_notes,
+            &utxos,
+            reservation_txid,
+            target_height,
+        )
+        .await;
 
         let orchard_anchor = if let Some(note) = orchard_notes.get(0) {
             note.witness.root()
@@ -1481,13 +2669,79 @@ impl LightWallet {
                     .unwrap()
                     .root()
             } else {
+                self.release_reserved_notes(&sapling_notes, &orchard_notes, &utxos, reservation_txid)
+                    .await;
                 return Err("No last known verified tree".to_string());
             }
         };
+
+        Ok(TransactionPlan {
+            target_height,
+            orchard_anchor: orchard::Anchor::from(orchard_anchor),
+            orchard_notes,
+            sapling_notes,
+            utxos,
+            outputs: recepients,
+            fee,
+            selected_value,
+            reservation_txid,
+            touched_pools,
+        })
+    }
+
+    /// Builds, proves, and broadcasts the transaction described by `plan`. This is the
+    /// local, immediate-signing counterpart to [`build_transaction_plan`]; an
+    /// air-gapped or hardware-wallet flow would instead hand `plan` to a signer that
+    /// holds the spending keys and returns the raw transaction bytes to broadcast here.
+    ///
+    /// [`build_transaction_plan`]: Self::build_transaction_plan
+    async fn build_and_broadcast_plan<F, Fut, P: TxProver>(
+        &self,
+        plan: TransactionPlan,
+        prover: P,
+        broadcast_fn: F,
+    ) -> Result<(String, Vec<u8>, u64), String>
+    where
+        F: Fn(Box<[u8]>) -> Fut,
+        Fut: Future<Output = Result<String, String>>,
+    {
+        let start_time = now();
+        let TransactionPlan {
+            target_height,
+            orchard_anchor,
+            orchard_notes,
+            sapling_notes,
+            utxos,
+            outputs: recepients,
+            fee,
+            selected_value,
+            reservation_txid,
+            ..
+        } = plan;
+
+        // Releases the notes/utxos `build_transaction_plan` reserved for us, for use on every
+        // error path between here and the point `transaction.txid()` takes over the reservation.
+        macro_rules! release_reservation_and_return {
+            ($e:expr) => {{
+                self.release_reserved_notes(&sapling_notes, &orchard_notes, &utxos, reservation_txid)
+                    .await;
+                return Err($e);
+            }};
+        }
+
+        // Create a map from address -> sk for all taddrs, so we can spend from the
+        // right address
+        let address_to_sk = self
+            .transaction_context
+            .keys
+            .read()
+            .await
+            .get_taddr_to_sk_map();
+
         let mut builder = Builder::with_orchard_anchor(
             self.transaction_context.config.chain,
             target_height,
-            orchard::Anchor::from(orchard_anchor),
+            orchard_anchor,
         );
         println!(
             "{}: Adding {} sapling notes, {} orchard notes, and {} utxos",
@@ -1498,7 +2752,7 @@ impl LightWallet {
         );
 
         // Add all tinputs
-        utxos
+        match utxos
             .iter()
             .map(|utxo| {
                 let outpoint: OutPoint = utxo.to_outpoint();
@@ -1522,7 +2776,11 @@ impl LightWallet {
                 }
             })
             .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| format!("{:?}", e))?;
+            .map_err(|e| format!("{:?}", e))
+        {
+            Ok(_) => {}
+            Err(e) => release_reservation_and_return!(e),
+        }
 
         for selected in sapling_notes.iter() {
             println!("Adding sapling spend");
@@ -1534,7 +2792,7 @@ impl LightWallet {
             ) {
                 let e = format!("Error adding note: {:?}", e);
                 error!("{}", e);
-                return Err(e);
+                release_reservation_and_return!(e);
             }
         }
 
@@ -1554,21 +2812,10 @@ impl LightWallet {
             ) {
                 let e = format!("Error adding note: {:?}", e);
                 error!("{}", e);
-                return Err(e);
+                release_reservation_and_return!(e);
             }
         }
 
-        //TODO: Send change to orchard instead of sapling
-        // If no Sapling notes were added, add the change address manually. That is,
-        // send the change to our sapling address manually. Note that if a sapling note was spent,
-        // the builder will automatically send change to that address
-        if sapling_notes.len() == 0 {
-            builder.send_change_to(
-                self.keys().read().await.zkeys[0].extfvk.fvk.ovk,
-                self.keys().read().await.zkeys[0].zaddress.clone(),
-            );
-        }
-
         // We'll use the first ovk to encrypt outgoing transactions
         let sapling_ovk = self.keys().read().await.zkeys[0].extfvk.fvk.ovk;
         let orchard_ovk = self
@@ -1579,6 +2826,42 @@ impl LightWallet {
             .get(0)
             .and_then(OrchardKey::ovk);
 
+        // Size and add the change output ourselves, rather than relying on the builder's own
+        // default-fee change computation (`send_change_to`/`send_orchard_change_to`, or its
+        // implicit change-to-first-spend-address fallback when a Sapling note was spent), so
+        // the built transaction actually pays the ZIP 317-style `fee` this plan was selected
+        // against instead of whatever flat fee the builder assumes. Prefer shielding change
+        // into Orchard when we hold an Orchard key, since that's the pool we'd rather
+        // accumulate notes in going forward; fall back to Sapling otherwise.
+        let total_output_value: u64 = recepients.iter().map(|(_, value, _)| u64::from(*value)).sum();
+        let change_value = u64::from(selected_value)
+            .checked_sub(total_output_value)
+            .and_then(|v| v.checked_sub(u64::from(fee)))
+            .expect("note/utxo selection already covers outputs plus fee");
+
+        if change_value > 0 {
+            let own_orchard_key = self.keys().read().await.okeys.get(0).cloned();
+            let result = match own_orchard_key.as_ref().and_then(OrchardKey::address) {
+                Some(change_address) => builder.add_orchard_output(
+                    orchard_ovk.clone(),
+                    change_address,
+                    change_value,
+                    MemoBytes::from(Memo::Empty),
+                ),
+                None => builder.add_sapling_output(
+                    Some(sapling_ovk),
+                    self.keys().read().await.zkeys[0].zaddress.clone(),
+                    Amount::from_u64(change_value).unwrap(),
+                    MemoBytes::from(Memo::Empty),
+                ),
+            };
+            if let Err(e) = result {
+                let e = format!("Error adding change output: {:?}", e);
+                error!("{}", e);
+                release_reservation_and_return!(e);
+            }
+        }
+
         let mut total_z_recepients = 0u32;
         for (to, value, memo) in recepients {
             // Compute memo if it exists
@@ -1591,7 +2874,7 @@ impl LightWallet {
                         Ok(m) => m,
                         Err(e) => {
                             error!("{}", e);
-                            return Err(e);
+                            release_reservation_and_return!(e);
                         }
                     }
                 }
@@ -1624,13 +2907,15 @@ impl LightWallet {
                             encoded_memo,
                         )
                     } else {
-                        return Err("Received UA with no Orchard or Sapling receiver".to_string());
+                        release_reservation_and_return!(
+                            "Received UA with no Orchard or Sapling receiver".to_string()
+                        );
                     }
                 }
             } {
                 let e = format!("Error adding output: {:?}", e);
                 error!("{}", e);
-                return Err(e);
+                release_reservation_and_return!(e);
             }
         }
 
@@ -1671,7 +2956,7 @@ impl LightWallet {
                 let e = format!("Error creating transaction: {:?}", e);
                 error!("{}", e);
                 self.send_progress.write().await.is_send_in_progress = false;
-                return Err(e);
+                release_reservation_and_return!(e);
             }
         };
 
@@ -1689,7 +2974,10 @@ impl LightWallet {
         let mut raw_transaction = vec![];
         transaction.write(&mut raw_transaction).unwrap();
 
-        let transaction_id = broadcast_fn(raw_transaction.clone().into_boxed_slice()).await?;
+        let transaction_id = match broadcast_fn(raw_transaction.clone().into_boxed_slice()).await {
+            Ok(transaction_id) => transaction_id,
+            Err(e) => release_reservation_and_return!(e),
+        };
 
         // Mark notes as spent.
         {
@@ -1752,7 +3040,7 @@ impl LightWallet {
                 .await;
         }
 
-        Ok((transaction_id, raw_transaction))
+        Ok((transaction_id, raw_transaction, u64::from(fee)))
     }
 
     pub async fn encrypt(&self, passwd: String) -> io::Result<()> {
@@ -1774,7 +3062,285 @@ impl LightWallet {
             .await
             .remove_encryption(passwd)
     }
+
+    /// Spawnable task that periodically streams the server's mempool, decrypting each
+    /// transaction with the wallet's viewing keys via the same fetch-and-scan path used for
+    /// confirmed blocks. Sends the txids we already hold in `mempool_transactions` as an
+    /// exclusion filter on every poll so we never re-download a transaction we've already seen.
+    /// Intended to be `tokio::spawn`ed alongside the block-sync task; runs until dropped.
+    pub async fn start_mempool_monitor(wallet: Arc<Self>, uri: http::Uri) {
+        loop {
+            tokio::time::sleep(MEMPOOL_MONITOR_POLL_INTERVAL).await;
+
+            let exclude = {
+                let known = wallet.mempool_transactions.read().await;
+                Exclude {
+                    txid: known.keys().map(|txid| txid.as_ref().to_vec()).collect(),
+                }
+            };
+
+            let mut client = match GrpcConnector::new(uri.clone()).get_client().await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Mempool monitor couldn't connect to {}: {}", uri, e);
+                    continue;
+                }
+            };
+
+            let mut stream = match client.get_mempool_tx(tonic::Request::new(exclude)).await {
+                Ok(response) => response.into_inner(),
+                Err(e) => {
+                    warn!("Mempool monitor couldn't fetch mempool from {}: {}", uri, e);
+                    continue;
+                }
+            };
+
+            let height = BlockHeight::from_u32(wallet.last_scanned_height().await as u32 + 1);
+            let price = wallet.price.read().await.clone();
+
+            while let Ok(Some(compact_tx)) = stream.message().await {
+                let Ok(txid_bytes) = <[u8; 32]>::try_from(compact_tx.hash.as_slice()) else {
+                    continue;
+                };
+                let txid = TxId::from_bytes(txid_bytes);
+
+                if wallet.mempool_transactions.read().await.contains_key(&txid) {
+                    continue;
+                }
+
+                // Fetch the full transaction and run it through the usual decrypt-and-record
+                // path, marking it unconfirmed just like a transaction we've just broadcast.
+                if wallet
+                    .transaction_context
+                    .fetch_full_transaction(
+                        &uri,
+                        &txid,
+                        height,
+                        true,
+                        now() as u32,
+                        TransactionMetadata::get_price(now(), &price),
+                    )
+                    .await
+                    .is_err()
+                {
+                    continue;
+                }
+
+                if let Some(metadata) = wallet
+                    .transaction_context
+                    .transaction_metadata_set
+                    .read()
+                    .await
+                    .current
+                    .get(&txid)
+                {
+                    wallet
+                        .mempool_transactions
+                        .write()
+                        .await
+                        .insert(txid, metadata.clone());
+                }
+            }
+
+            wallet.expire_stale_unconfirmed_spends(u32::from(height)).await;
+        }
+    }
+
+    /// Clears `unconfirmed_spent` on any note/UTXO whose spending transaction is no longer in
+    /// the mempool and has fallen past its expiry height, so those funds become spendable
+    /// again instead of staying locked on a transaction that will never confirm.
+    async fn expire_stale_unconfirmed_spends(&self, current_height: u32) {
+        let mempool = self.mempool_transactions.read().await;
+        let mut transactions = self.transaction_context.transaction_metadata_set.write().await;
+
+        let is_expired = |spend: &Option<(TxId, u32)>| match spend {
+            Some((spent_txid, target_height)) => {
+                !mempool.contains_key(spent_txid)
+                    && current_height > target_height + DEFAULT_TX_EXPIRY_DELTA
+            }
+            None => false,
+        };
+
+        for wtx in transactions.current.values_mut() {
+            for nd in wtx.sapling_notes.iter_mut() {
+                if is_expired(&nd.unconfirmed_spent) {
+                    nd.unconfirmed_spent = None;
+                }
+            }
+            for nd in wtx.orchard_notes.iter_mut() {
+                if is_expired(&nd.unconfirmed_spent) {
+                    nd.unconfirmed_spent = None;
+                }
+            }
+            for utxo in wtx.utxos.iter_mut() {
+                if is_expired(&utxo.unconfirmed_spent) {
+                    utxo.unconfirmed_spent = None;
+                }
+            }
+        }
+    }
+
+    /// Unconfirmed transactions currently tracked by the mempool monitor, in the same shape the
+    /// `current` listing uses so a UI can merge the two with an `unconfirmed` flag.
+    pub async fn get_mempool_transactions(&self) -> Vec<MempoolTransactionSummary> {
+        self.mempool_transactions
+            .read()
+            .await
+            .values()
+            .map(|wtx| MempoolTransactionSummary {
+                txid: wtx.txid,
+                datetime: wtx.datetime,
+                unconfirmed: true,
+                amount: -((wtx.get_transaction_fee().unwrap_or(0)
+                    + wtx
+                        .outgoing_metadata
+                        .iter()
+                        .map(|om| om.value)
+                        .sum::<u64>()) as i64),
+            })
+            .collect()
+    }
+}
+
+/// One row of the unconfirmed-transaction listing surfaced by `get_mempool_transactions`.
+/// Mirrors the shape of the confirmed-transaction listing, with `amount` reported negative
+/// (fee plus the sum of outgoing values) the way an outgoing send is reported before it's mined.
+#[derive(Debug, Clone)]
+pub struct MempoolTransactionSummary {
+    pub txid: TxId,
+    pub datetime: u64,
+    pub unconfirmed: bool,
+    pub amount: i64,
+}
+
+/// ZIP 321 payment-URI parsing, delegating entirely to the audited
+/// [`zcash_client_backend::zip321::TransactionRequest`] parser rather than reimplementing the
+/// spec by hand. Turns a `zcash:` URI into the `(address, amount, memo)` tuples
+/// [`LightWallet::send_to_address`] expects.
+pub mod zip321 {
+    use super::*;
+    use zcash_client_backend::zip321::TransactionRequest;
+
+    /// Parses a ZIP 321 payment URI (or a bare address, as a single zero-amount payment) into
+    /// the `(address, amount, memo)` tuples `send_to_address` expects. A payment's memo is kept
+    /// only when it decodes as a UTF-8 text memo; any other memo kind is dropped, matching
+    /// [`LightWallet::memo_str`].
+    pub fn parse_payment_uri(
+        chain: &impl zcash_primitives::consensus::Parameters,
+        uri: &str,
+    ) -> Result<Vec<(String, u64, Option<String>)>, String> {
+        let request = TransactionRequest::from_uri(chain, uri)
+            .map_err(|e| format!("Invalid ZIP 321 payment URI: {}", e))?;
+
+        Ok(request
+            .payments()
+            .values()
+            .map(|payment| {
+                let address = payment.recipient_address.encode();
+                let amount = u64::from(payment.amount);
+                let memo = payment
+                    .memo
+                    .clone()
+                    .and_then(|bytes| Memo::try_from(bytes).ok())
+                    .and_then(|memo| LightWallet::memo_str(Some(memo)));
+                (address, amount, memo)
+            })
+            .collect())
+    }
+}
+
+/// ZIP 317-style conventional fee: `marginal_fee` zats per logical action, with the first
+/// `grace_actions` actions free of charge. A logical action is, per pool (transparent counting
+/// inputs and outputs as its "spends" and "outputs"), the larger of the spend count and the
+/// output count, since padding means a pool's spends and outputs don't each cost a separate
+/// action.
+fn conventional_fee(
+    transparent_inputs: usize,
+    transparent_outputs: usize,
+    sapling_spends: usize,
+    sapling_outputs: usize,
+    orchard_spends: usize,
+    orchard_outputs: usize,
+) -> Amount {
+    const MARGINAL_FEE: u64 = 5_000;
+    const GRACE_ACTIONS: u64 = 2;
+
+    let logical_actions = transparent_inputs.max(transparent_outputs) as u64
+        + sapling_spends.max(sapling_outputs) as u64
+        + orchard_spends.max(orchard_outputs) as u64;
+
+    Amount::from_u64(MARGINAL_FEE * logical_actions.max(GRACE_ACTIONS)).unwrap()
+}
+
+/// Depth-first include/exclude search over `values` (sorted descending) for a subset landing in
+/// `[target, window_high]`. `suffix_total[i]` is the sum of `values[i..]`, used to prune a branch
+/// once even including every remaining candidate couldn't reach `target`. Among subsets in the
+/// window, prefers the one found first while considering fewer candidates, which (since `values`
+/// is sorted descending and this explores "include" before "exclude" at each step) favors fewer,
+/// larger notes.
+///
+/// `tries` bounds the number of nodes visited, mirroring Bitcoin Core's BnB implementation:
+/// pruning alone isn't enough to guarantee a fast exit on adversarial inputs, so the search gives
+/// up (returning whatever `best` it already has) once the budget is exhausted.
+#[allow(clippy::too_many_arguments)]
+fn branch_and_bound_search(
+    values: &[u64],
+    suffix_total: &[u64],
+    position: usize,
+    selected_total: u64,
+    selected: &mut Vec<usize>,
+    target: u64,
+    window_high: u64,
+    best: &mut Option<Vec<usize>>,
+    tries: &mut u32,
+) {
+    if *tries == 0 {
+        return;
+    }
+    *tries -= 1;
+
+    if selected_total > window_high {
+        return;
+    }
+    if selected_total >= target {
+        if best.is_none() || selected.len() < best.as_ref().unwrap().len() {
+            *best = Some(selected.clone());
+        }
+        return;
+    }
+    if position == values.len() || selected_total + suffix_total[position] < target {
+        return;
+    }
+
+    // Include values[position].
+    selected.push(position);
+    branch_and_bound_search(
+        values,
+        suffix_total,
+        position + 1,
+        selected_total + values[position],
+        selected,
+        target,
+        window_high,
+        best,
+        tries,
+    );
+    selected.pop();
+
+    // Exclude values[position].
+    branch_and_bound_search(
+        values,
+        suffix_total,
+        position + 1,
+        selected_total,
+        selected,
+        target,
+        window_high,
+        best,
+        tries,
+    );
 }
+
 fn decode_orchard_spending_key(
     expected_hrp: &str,
     s: &str,
@@ -1802,6 +3368,7 @@ fn decode_orchard_spending_key(
 
 #[cfg(test)]
 mod test {
+    use zcash_primitives::consensus::BlockHeight;
     use zcash_primitives::transaction::components::Amount;
 
     use crate::{
@@ -1813,6 +3380,65 @@ mod test {
         },
     };
 
+    mod scan_ranges {
+        use super::*;
+
+        #[test]
+        fn is_contiguous_true_only_within_a_single_recorded_range() {
+            let mut ranges = ScanRanges::new();
+            ranges.insert_scanned_range(BlockHeight::from_u32(100), BlockHeight::from_u32(200));
+            ranges.insert_scanned_range(BlockHeight::from_u32(250), BlockHeight::from_u32(300));
+
+            assert!(ranges.is_contiguous(BlockHeight::from_u32(120), BlockHeight::from_u32(180)));
+            assert!(!ranges.is_contiguous(BlockHeight::from_u32(180), BlockHeight::from_u32(260)));
+        }
+
+        #[test]
+        fn insert_scanned_range_merges_adjacent_and_overlapping_ranges() {
+            let mut ranges = ScanRanges::new();
+            ranges.insert_scanned_range(BlockHeight::from_u32(100), BlockHeight::from_u32(150));
+            ranges.insert_scanned_range(BlockHeight::from_u32(151), BlockHeight::from_u32(200));
+            ranges.insert_scanned_range(BlockHeight::from_u32(180), BlockHeight::from_u32(220));
+
+            assert!(ranges.is_contiguous(BlockHeight::from_u32(100), BlockHeight::from_u32(220)));
+        }
+
+        #[test]
+        fn suggest_scan_ranges_orders_tip_gap_first_then_older_gaps_oldest_last() {
+            let mut ranges = ScanRanges::new();
+            ranges.insert_scanned_range(BlockHeight::from_u32(100), BlockHeight::from_u32(150));
+            ranges.insert_scanned_range(BlockHeight::from_u32(200), BlockHeight::from_u32(250));
+            ranges.insert_scanned_range(BlockHeight::from_u32(300), BlockHeight::from_u32(350));
+
+            let suggestions = ranges.suggest_scan_ranges(BlockHeight::from_u32(400));
+
+            assert_eq!(
+                suggestions,
+                vec![
+                    (BlockHeight::from_u32(351), BlockHeight::from_u32(400)),
+                    (BlockHeight::from_u32(251), BlockHeight::from_u32(299)),
+                    (BlockHeight::from_u32(151), BlockHeight::from_u32(199)),
+                ]
+            );
+        }
+
+        #[test]
+        fn insert_boundary_is_retrievable_by_height() {
+            let mut ranges = ScanRanges::new();
+            ranges.insert_boundary(BlockBoundaryMeta {
+                height: BlockHeight::from_u32(100),
+                hash: "deadbeef".to_string(),
+                sapling_tree_size: 1,
+                orchard_tree_size: 2,
+            });
+
+            assert_eq!(
+                ranges.boundaries[&BlockHeight::from_u32(100)].hash,
+                "deadbeef"
+            );
+        }
+    }
+
     mod bench_select_notes_and_utxos {
         use super::*;
         crate::apply_scenario! {insufficient_funds_0_present_needed_1 10}
@@ -1820,7 +3446,7 @@ mod test {
             let NBlockFCBLScenario { lightclient, .. } = scenario;
             let sufficient_funds = lightclient
                 .wallet
-                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false)
+                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false, NoteSelectionStrategy::GreedyAscending, None)
                 .await;
             assert_eq!(Amount::from_u64(0).unwrap(), sufficient_funds.3);
         }
@@ -1851,7 +3477,7 @@ mod test {
             );
             let sufficient_funds = lightclient
                 .wallet
-                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false)
+                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false, NoteSelectionStrategy::GreedyAscending, None)
                 .await;
             assert_eq!(Amount::from_u64(0).unwrap(), sufficient_funds.3);
         }
@@ -1886,7 +3512,7 @@ mod test {
             );
             let sufficient_funds = lightclient
                 .wallet
-                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false)
+                .select_notes_and_utxos(Amount::from_u64(1).unwrap(), false, false, false, NoteSelectionStrategy::GreedyAscending, Some(5))
                 .await;
             assert_eq!(Amount::from_u64(1_001).unwrap(), sufficient_funds.3);
         }
@@ -1915,13 +3541,19 @@ mod test {
 
         assert_eq!(lightclient.wallet.last_scanned_height().await, 11);
 
-        // 3. With one confirmation, we should be able to select the note
+        // 3. With one confirmation, minconf=0 clamps the anchor depth down to the note's own
+        // confirmation count, so it should be selectable immediately.
         let amt = Amount::from_u64(10_000).unwrap();
-        // Reset the anchor offsets
-        lightclient.wallet.transaction_context.config.anchor_offset = [9, 4, 2, 1, 0];
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
             .await;
         assert!(selected >= amt);
         assert_eq!(sapling_notes.len(), 1);
@@ -1946,11 +3578,18 @@ mod test {
             )
         );
 
-        // With min anchor_offset at 1, we can't select any notes
-        lightclient.wallet.transaction_context.config.anchor_offset = [9, 4, 2, 1, 1];
+        // With minconf at 2, the note needs a second confirmation beyond its own mined block,
+        // which it doesn't have yet, so we can't select it
         let (_orchard_notes, sapling_notes, utxos, _selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(2),
+            )
             .await;
         assert_eq!(sapling_notes.len(), 0);
         assert_eq!(utxos.len(), 0);
@@ -1961,7 +3600,14 @@ mod test {
 
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(2),
+            )
             .await;
         assert!(selected >= amt);
         assert_eq!(sapling_notes.len(), 1);
@@ -1986,13 +3632,20 @@ mod test {
             )
         );
 
-        // Mine 15 blocks, then selecting the note should result in witness only 10 blocks deep
+        // Mine 15 more blocks; with no minconf passed, selection uses the default anchor depth
+        // (10 blocks), so the witness should be exactly 9 blocks back from the tip.
         mine_numblocks_each_with_two_sap_txs(&mut fake_compactblock_list, &data, &lightclient, 15)
             .await;
-        lightclient.wallet.transaction_context.config.anchor_offset = [9, 4, 2, 1, 1];
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, true, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                true,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                None,
+            )
             .await;
         assert!(selected >= amt);
         assert_eq!(sapling_notes.len(), 1);
@@ -2021,7 +3674,14 @@ mod test {
         let amt = Amount::from_u64(1_000_000).unwrap();
         let (_orchard_notes, sapling_notes, utxos, _selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                None,
+            )
             .await;
         assert_eq!(sapling_notes.len(), 0);
         assert_eq!(utxos.len(), 0);
@@ -2041,7 +3701,14 @@ mod test {
         let amt = Amount::from_u64(value + tvalue - 10_000).unwrap();
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, true, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                true,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                None,
+            )
             .await;
         assert_eq!(selected, Amount::from_u64(value + tvalue).unwrap());
         assert_eq!(sapling_notes.len(), 1);
@@ -2051,18 +3718,32 @@ mod test {
         let amt = Amount::from_u64(tvalue - 10_000).unwrap();
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, true, true, false)
+            .select_notes_and_utxos(
+                amt,
+                true,
+                true,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                None,
+            )
             .await;
         assert_eq!(selected, Amount::from_u64(tvalue).unwrap());
         assert_eq!(sapling_notes.len(), 0);
         assert_eq!(utxos.len(), 1);
 
-        // Set min confs to 5, so the sapling note will not be selected
-        lightclient.wallet.transaction_context.config.anchor_offset = [9, 4, 4, 4, 4];
+        // The shielded amount requested is already covered by the transparent utxo alone, so no
+        // sapling note needs to be touched regardless of its confirmation depth
         let amt = Amount::from_u64(tvalue - 10_000).unwrap();
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, true, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                true,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                None,
+            )
             .await;
         assert_eq!(selected, Amount::from_u64(tvalue).unwrap());
         assert_eq!(sapling_notes.len(), 0);
@@ -2093,13 +3774,18 @@ mod test {
 
         assert_eq!(lightclient.wallet.last_scanned_height().await, 11);
 
-        // 3. With one confirmation, we should be able to select the note
+        // 3. With one confirmation, minconf=0 clamps the anchor depth down so we can select the note
         let amt = Amount::from_u64(10_000).unwrap();
-        // Reset the anchor offsets
-        lightclient.wallet.transaction_context.config.anchor_offset = [9, 4, 2, 1, 0];
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
             .await;
         assert!(selected >= amt);
         assert_eq!(sapling_notes.len(), 1);
@@ -2138,7 +3824,14 @@ mod test {
         let amt = Amount::from_u64(10_000).unwrap();
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
             .await;
         assert!(selected >= amt);
         assert_eq!(sapling_notes.len(), 1);
@@ -2149,12 +3842,172 @@ mod test {
         let amt = Amount::from_u64(value1 + value2).unwrap();
         let (_orchard_notes, sapling_notes, utxos, selected) = lightclient
             .wallet
-            .select_notes_and_utxos(amt, false, false, false)
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
             .await;
         assert!(selected == amt);
         assert_eq!(sapling_notes.len(), 2);
         assert_eq!(utxos.len(), 0);
     }
+
+    apply_scenario! {reservation_excludes_note_from_concurrent_selection 10}
+    async fn reservation_excludes_note_from_concurrent_selection(scenario: NBlockFCBLScenario) {
+        let NBlockFCBLScenario {
+            data,
+            lightclient,
+            mut fake_compactblock_list,
+            ..
+        } = scenario;
+        let extfvk1 = lightclient
+            .wallet
+            .keys()
+            .read()
+            .await
+            .get_all_sapling_extfvks()[0]
+            .clone();
+        let value = 100_000;
+        fake_compactblock_list.create_coinbase_transaction(&extfvk1, value);
+        mine_pending_blocks(&mut fake_compactblock_list, &data, &lightclient).await;
+
+        let amt = Amount::from_u64(10_000).unwrap();
+        let (orchard_notes, sapling_notes, utxos, selected) = lightclient
+            .wallet
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
+            .await;
+        assert!(selected >= amt);
+        assert_eq!(sapling_notes.len(), 1);
+
+        // Simulate a concurrent `send_to_address` call reserving these notes right after
+        // selecting them, before it has built (and so doesn't yet have a real txid for) its
+        // transaction.
+        let target_height =
+            BlockHeight::from_u32(lightclient.wallet.get_target_height().await.unwrap());
+        let reservation_txid = lightclient.wallet.next_reservation_txid();
+        lightclient
+            .wallet
+            .reserve_selected_notes(
+                &sapling_notes,
+                &orchard_notes,
+                &utxos,
+                reservation_txid,
+                target_height,
+            )
+            .await;
+
+        // A second selection issued while the first send is still in flight must not pick the
+        // same note.
+        let (_orchard_notes, second_sapling_notes, _utxos, second_selected) = lightclient
+            .wallet
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
+            .await;
+        assert_eq!(second_selected, Amount::from_u64(0).unwrap());
+        assert_eq!(second_sapling_notes.len(), 0);
+
+        // Releasing the reservation (e.g. because the first send failed before broadcasting)
+        // makes the note selectable again.
+        lightclient
+            .wallet
+            .release_reserved_notes(&sapling_notes, &orchard_notes, &utxos, reservation_txid)
+            .await;
+        let (_orchard_notes, third_sapling_notes, _utxos, third_selected) = lightclient
+            .wallet
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
+            .await;
+        assert!(third_selected >= amt);
+        assert_eq!(third_sapling_notes.len(), 1);
+    }
+
+    apply_scenario! {orchard_note_selection 10}
+    async fn orchard_note_selection(scenario: NBlockFCBLScenario) {
+        let NBlockFCBLScenario {
+            data,
+            mut lightclient,
+            mut fake_compactblock_list,
+            ..
+        } = scenario;
+        // Fund an Orchard note, mirroring multi_z_note_selection's sapling setup.
+        let okey1 = lightclient
+            .wallet
+            .keys()
+            .read()
+            .await
+            .okeys
+            .get(0)
+            .cloned()
+            .unwrap();
+        let value = 100_000;
+        let (transaction, _height, _) =
+            fake_compactblock_list.create_orchard_coinbase_transaction(&okey1, value);
+        let txid = transaction.txid();
+        mine_pending_blocks(&mut fake_compactblock_list, &data, &lightclient).await;
+
+        assert_eq!(lightclient.wallet.last_scanned_height().await, 11);
+
+        // With one confirmation, minconf=0 clamps the anchor depth down so the note is
+        // witnessed and selectable.
+        let amt = Amount::from_u64(10_000).unwrap();
+        let (orchard_notes, sapling_notes, utxos, selected) = lightclient
+            .wallet
+            .select_notes_and_utxos(
+                amt,
+                false,
+                false,
+                false,
+                NoteSelectionStrategy::GreedyAscending,
+                Some(0),
+            )
+            .await;
+        assert!(selected >= amt);
+        assert_eq!(sapling_notes.len(), 0);
+        assert_eq!(utxos.len(), 0);
+        assert_eq!(orchard_notes.len(), 1);
+        assert_eq!(orchard_notes[0].note.value().inner(), value);
+        assert_eq!(
+            incw_to_string(&orchard_notes[0].witness),
+            incw_to_string(
+                lightclient
+                    .wallet
+                    .transaction_context
+                    .transaction_metadata_set
+                    .read()
+                    .await
+                    .current
+                    .get(&txid)
+                    .unwrap()
+                    .orchard_notes[0]
+                    .witnesses
+                    .last()
+                    .unwrap()
+            )
+        );
+    }
     const FINAL_ROOT: &'static str =
         "1d44048a01f1c7a8958dd2927912f1c02ad10ed916877e1fd2c0a07764850a60";
     const      TREE_STATE: &'static str = "01a682706317caa5aec999385ac580445ff4eff6347e4a3c844ac18fcb5fe9bf1c01cca6f37237f27037fa7f8fe5ec8d2cc251b791cfb9cdd08cd1215229fa9435221f0001590d3e7e3f4cd572274f79f4a95b41fa72ed9b42a7c6dbcaec9637eaf368ac0e0000018843337920418307fa7699d506bb0f47a79aea7f6fe8efc1e25b9dde8966e22f013b5a8ef020d8b30fa8beb8406dd30b2a1944755f5549713e4fe24de78ab72e12000001a46523754a6d3fbc3226d6221dafca357d930e183297a0ba1cfa2db5d0500e1f01b6fd291e9d6068bc24e99aefe49f8f29836ed1223deabc23871f1a1288f9240300016fc552915a0d5bc5c0c0cdf29453edf081d9a2de396535e6084770c38dcff838019518d88883e466a41ca67d6b986739fb2f601d77bb957398ed899de70b2a9f0801cd4871c1f545e7f5d844cc65fb00b8a162e316c3d1a435b00c435032b732c4280000000000000000000000000000000000";
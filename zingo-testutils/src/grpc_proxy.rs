@@ -1,6 +1,7 @@
 use std::{
+    collections::HashMap,
     net::SocketAddr,
-    sync::{atomic::AtomicBool, Arc},
+    sync::{atomic::AtomicBool, Arc, Mutex},
 };
 
 use zingolib::compact_formats::{
@@ -11,6 +12,72 @@ use zingolib::compact_formats::{
     TreeState, TxFilter,
 };
 
+/// Configurable fault injection shared by a [`ProxyServer`], so tests can validate
+/// client resilience against conditions real clients have had to harden against:
+/// added latency, errors returned after N successful calls, and truncated streams.
+#[derive(Default)]
+pub struct FaultConfig {
+    /// Added latency injected before every passed-through call.
+    added_latency: Mutex<Option<std::time::Duration>>,
+    /// method name -> (remaining successes before failing, status code to return)
+    fail_after: Mutex<HashMap<&'static str, (u32, tonic::Code)>>,
+    /// method name -> number of streamed items to emit before truncating the response
+    truncate_after: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl FaultConfig {
+    /// Injects `latency` before every subsequent passthrough call.
+    pub fn set_added_latency(&self, latency: std::time::Duration) {
+        *self.added_latency.lock().unwrap() = Some(latency);
+    }
+
+    /// After `n_successes` further calls to `method` succeed, return `code` instead of
+    /// passing the call through.
+    pub fn fail_after(&self, method: &'static str, n_successes: u32, code: tonic::Code) {
+        self.fail_after
+            .lock()
+            .unwrap()
+            .insert(method, (n_successes, code));
+    }
+
+    /// Truncate a streaming response from `method` to at most `n_items`. Only
+    /// `get_block_range` and `get_mempool_stream` apply this limit; other methods ignore it.
+    pub fn truncate_after(&self, method: &'static str, n_items: usize) {
+        self.truncate_after
+            .lock()
+            .unwrap()
+            .insert(method, n_items);
+    }
+
+    /// Truncation limit configured for `method`, if any.
+    pub fn truncation_limit(&self, method: &str) -> Option<usize> {
+        self.truncate_after.lock().unwrap().get(method).copied()
+    }
+
+    async fn apply_latency(&self) {
+        let latency = *self.added_latency.lock().unwrap();
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+    }
+
+    /// Consults and advances the failure schedule for `method`, returning the status
+    /// to respond with once its successful-call budget is exhausted.
+    fn take_failure(&self, method: &str) -> Option<tonic::Status> {
+        let mut fail_after = self.fail_after.lock().unwrap();
+        let (remaining, code) = fail_after.get_mut(method)?;
+        if *remaining == 0 {
+            Some(tonic::Status::new(
+                *code,
+                format!("fault-injected failure for {method}"),
+            ))
+        } else {
+            *remaining -= 1;
+            None
+        }
+    }
+}
+
 macro_rules! define_grpc_passthrough {
     (fn
         $name:ident(
@@ -36,6 +103,10 @@ macro_rules! define_grpc_passthrough {
                 while !$self.online.load(::core::sync::atomic::Ordering::Relaxed) {
                     ::tokio::time::sleep(::core::time::Duration::from_millis(50)).await;
                 }
+                $self.faults.apply_latency().await;
+                if let Some(status) = $self.faults.take_failure(stringify!($name)) {
+                    return Err(status);
+                }
                 println!("Proxy passing through {} call", stringify!($name));
                 ::zingolib::grpc_connector::GrpcConnector::new($self.lightwalletd_uri.clone())
                     .get_client()
@@ -48,9 +119,64 @@ macro_rules! define_grpc_passthrough {
     };
 }
 
+/// Shared store of transactions captured by [`ProxyServer::send_transaction`], so tests
+/// can assert exactly what a wallet broadcasts and simulate a mempool that already
+/// contains a just-sent transaction.
+#[derive(Default, Clone)]
+pub struct RecordedTransactions {
+    transactions: Arc<Mutex<Vec<RawTransaction>>>,
+    /// When set, `get_mempool_tx`/`get_mempool_stream` serve the recorded transactions
+    /// back instead of passing the call through to the upstream lightwalletd.
+    replay: Arc<AtomicBool>,
+}
+
+impl RecordedTransactions {
+    /// Records a transaction submitted through `send_transaction`.
+    fn record(&self, transaction: RawTransaction) {
+        self.transactions.lock().unwrap().push(transaction);
+    }
+
+    /// A snapshot of every transaction recorded so far.
+    pub fn snapshot(&self) -> Vec<RawTransaction> {
+        self.transactions.lock().unwrap().clone()
+    }
+
+    /// Enables or disables serving `get_mempool_tx`/`get_mempool_stream` from the
+    /// recorded transactions instead of the upstream lightwalletd.
+    pub fn set_replay(&self, replay: bool) {
+        self.replay
+            .store(replay, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn is_replaying(&self) -> bool {
+        self.replay.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Best-effort projection of a recorded [`RawTransaction`] into a [`CompactTx`], enough
+/// for tests asserting presence/identity of a just-sent transaction in the mempool feed.
+fn raw_transaction_to_compact_tx(raw: &RawTransaction) -> CompactTx {
+    let hash = zcash_primitives::transaction::Transaction::read(
+        &raw.data[..],
+        zcash_primitives::consensus::BranchId::Nu5,
+    )
+    .map(|transaction| transaction.txid().as_ref().to_vec())
+    .unwrap_or_default();
+    CompactTx {
+        index: 0,
+        hash,
+        fee: 0,
+        spends: vec![],
+        outputs: vec![],
+        actions: vec![],
+    }
+}
+
 pub struct ProxyServer {
     pub lightwalletd_uri: http::Uri,
     pub online: Arc<AtomicBool>,
+    pub faults: Arc<FaultConfig>,
+    pub recorded: RecordedTransactions,
 }
 
 impl ProxyServer {
@@ -84,14 +210,52 @@ impl CompactTxStreamer for ProxyServer {
     );
 
     #[doc = "Server streaming response type for the GetBlockRange method."]
-    type GetBlockRangeStream = tonic::Streaming<CompactBlock>;
+    type GetBlockRangeStream =
+        ::std::pin::Pin<Box<dyn futures::Stream<Item = Result<CompactBlock, tonic::Status>> + Send>>;
 
-    define_grpc_passthrough!(
-        fn get_block_range(
-            &self,
-            request: tonic::Request<BlockRange>,
-        ) -> Self::GetBlockRangeStream
-    );
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    fn get_block_range<'life0, 'async_trait>(
+        &'life0 self,
+        request: tonic::Request<BlockRange>,
+    ) -> ::core::pin::Pin<
+        Box<
+            dyn ::core::future::Future<
+                    Output = Result<tonic::Response<Self::GetBlockRangeStream>, tonic::Status>,
+                > + ::core::marker::Send
+                + 'async_trait,
+        >,
+    >
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            while !self.online.load(::core::sync::atomic::Ordering::Relaxed) {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            self.faults.apply_latency().await;
+            if let Some(status) = self.faults.take_failure("get_block_range") {
+                return Err(status);
+            }
+            println!("Proxy passing through get_block_range call");
+            let response = ::zingolib::grpc_connector::GrpcConnector::new(
+                self.lightwalletd_uri.clone(),
+            )
+            .get_client()
+            .await
+            .expect("Proxy server failed to create client")
+            .get_block_range(request)
+            .await?;
+            let limit = self.faults.truncation_limit("get_block_range");
+            let (metadata, stream, extensions) = response.into_parts();
+            let stream: Self::GetBlockRangeStream = match limit {
+                Some(n) => Box::pin(futures::StreamExt::take(stream, n)),
+                None => Box::pin(stream),
+            };
+            Ok(tonic::Response::from_parts(metadata, stream, extensions))
+        })
+    }
 
     define_grpc_passthrough!(
         fn get_transaction(
@@ -100,12 +264,40 @@ impl CompactTxStreamer for ProxyServer {
         ) -> RawTransaction
     );
 
-    define_grpc_passthrough!(
-        fn send_transaction(
-            &self,
-            request: tonic::Request<RawTransaction>,
-        ) -> SendResponse
-    );
+    #[must_use]
+    #[allow(clippy::type_complexity, clippy::type_repetition_in_bounds)]
+    fn send_transaction<'life0, 'async_trait>(
+        &'life0 self,
+        request: tonic::Request<RawTransaction>,
+    ) -> ::core::pin::Pin<
+        Box<
+            dyn ::core::future::Future<Output = Result<tonic::Response<SendResponse>, tonic::Status>>
+                + ::core::marker::Send
+                + 'async_trait,
+        >,
+    >
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            while !self.online.load(::core::sync::atomic::Ordering::Relaxed) {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            self.faults.apply_latency().await;
+            if let Some(status) = self.faults.take_failure("send_transaction") {
+                return Err(status);
+            }
+            self.recorded.record(request.get_ref().clone());
+            println!("Proxy passing through send_transaction call");
+            ::zingolib::grpc_connector::GrpcConnector::new(self.lightwalletd_uri.clone())
+                .get_client()
+                .await
+                .expect("Proxy server failed to create client")
+                .send_transaction(request)
+                .await
+        })
+    }
 
     #[doc = "Server streaming response type for the GetTaddressTxids method."]
     type GetTaddressTxidsStream = tonic::Streaming<RawTransaction>;
@@ -145,24 +337,113 @@ impl CompactTxStreamer for ProxyServer {
     }
 
     #[doc = "Server streaming response type for the GetMempoolTx method."]
-    type GetMempoolTxStream = tonic::Streaming<CompactTx>;
+    type GetMempoolTxStream =
+        ::std::pin::Pin<Box<dyn futures::Stream<Item = Result<CompactTx, tonic::Status>> + Send>>;
 
-    define_grpc_passthrough!(
-        fn get_mempool_tx(
-            &self,
-            request: tonic::Request<Exclude>,
-        ) -> Self::GetMempoolTxStream
-    );
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    fn get_mempool_tx<'life0, 'async_trait>(
+        &'life0 self,
+        request: tonic::Request<Exclude>,
+    ) -> ::core::pin::Pin<
+        Box<
+            dyn ::core::future::Future<
+                    Output = Result<tonic::Response<Self::GetMempoolTxStream>, tonic::Status>,
+                > + ::core::marker::Send
+                + 'async_trait,
+        >,
+    >
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            while !self.online.load(::core::sync::atomic::Ordering::Relaxed) {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            self.faults.apply_latency().await;
+            if let Some(status) = self.faults.take_failure("get_mempool_tx") {
+                return Err(status);
+            }
+            if self.recorded.is_replaying() {
+                println!("Proxy serving get_mempool_tx from recorded transactions");
+                let compact_txs: Vec<_> = self
+                    .recorded
+                    .snapshot()
+                    .iter()
+                    .map(|raw| Ok(raw_transaction_to_compact_tx(raw)))
+                    .collect();
+                let stream: Self::GetMempoolTxStream = Box::pin(futures::stream::iter(compact_txs));
+                return Ok(tonic::Response::new(stream));
+            }
+            println!("Proxy passing through get_mempool_tx call");
+            let response = ::zingolib::grpc_connector::GrpcConnector::new(
+                self.lightwalletd_uri.clone(),
+            )
+            .get_client()
+            .await
+            .expect("Proxy server failed to create client")
+            .get_mempool_tx(request)
+            .await?;
+            let (metadata, stream, extensions) = response.into_parts();
+            let stream: Self::GetMempoolTxStream = Box::pin(stream);
+            Ok(tonic::Response::from_parts(metadata, stream, extensions))
+        })
+    }
 
     #[doc = "Server streaming response type for the GetMempoolStream method."]
-    type GetMempoolStreamStream = tonic::Streaming<RawTransaction>;
+    type GetMempoolStreamStream =
+        ::std::pin::Pin<Box<dyn futures::Stream<Item = Result<RawTransaction, tonic::Status>> + Send>>;
 
-    define_grpc_passthrough!(
-        fn get_mempool_stream(
-            &self,
-            request: tonic::Request<Empty>,
-        ) -> Self::GetMempoolStreamStream
-    );
+    #[must_use]
+    #[allow(clippy::type_complexity)]
+    fn get_mempool_stream<'life0, 'async_trait>(
+        &'life0 self,
+        request: tonic::Request<Empty>,
+    ) -> ::core::pin::Pin<
+        Box<
+            dyn ::core::future::Future<
+                    Output = Result<tonic::Response<Self::GetMempoolStreamStream>, tonic::Status>,
+                > + ::core::marker::Send
+                + 'async_trait,
+        >,
+    >
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        Box::pin(async move {
+            while !self.online.load(::core::sync::atomic::Ordering::Relaxed) {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            self.faults.apply_latency().await;
+            if let Some(status) = self.faults.take_failure("get_mempool_stream") {
+                return Err(status);
+            }
+            if self.recorded.is_replaying() {
+                println!("Proxy serving get_mempool_stream from recorded transactions");
+                let recorded: Vec<_> = self.recorded.snapshot().into_iter().map(Ok).collect();
+                let stream: Self::GetMempoolStreamStream = Box::pin(futures::stream::iter(recorded));
+                return Ok(tonic::Response::new(stream));
+            }
+            println!("Proxy passing through get_mempool_stream call");
+            let response = ::zingolib::grpc_connector::GrpcConnector::new(
+                self.lightwalletd_uri.clone(),
+            )
+            .get_client()
+            .await
+            .expect("Proxy server failed to create client")
+            .get_mempool_stream(request)
+            .await?;
+            let limit = self.faults.truncation_limit("get_mempool_stream");
+            let (metadata, stream, extensions) = response.into_parts();
+            let stream: Self::GetMempoolStreamStream = match limit {
+                Some(n) => Box::pin(futures::StreamExt::take(stream, n)),
+                None => Box::pin(stream),
+            };
+            Ok(tonic::Response::from_parts(metadata, stream, extensions))
+        })
+    }
 
     define_grpc_passthrough!(
         fn get_tree_state(
@@ -151,6 +151,82 @@ mod sapling_crypto_note {
     }
 }
 
+/// Transparent Output Mocker
+pub mod transparent_output {
+    //! Mocks a [`zcash_client_backend::wallet::WalletTransparentOutput`].
+
+    use zcash_client_backend::wallet::WalletTransparentOutput;
+    use zcash_primitives::{
+        consensus::BlockHeight,
+        legacy::TransparentAddress,
+        transaction::components::{amount::NonNegativeAmount, OutPoint, TxOut},
+    };
+
+    use super::build_method;
+
+    /// Any old transparent address, for mocking.
+    pub fn default_transparent_address() -> TransparentAddress {
+        TransparentAddress::PublicKeyHash([7u8; 20])
+    }
+
+    /// A struct to build a mock [`zcash_client_backend::wallet::WalletTransparentOutput`]
+    /// from scratch.
+    pub struct WalletTransparentOutputBuilder {
+        outpoint: Option<OutPoint>,
+        value: Option<NonNegativeAmount>,
+        address: Option<TransparentAddress>,
+        height: Option<BlockHeight>,
+    }
+
+    impl WalletTransparentOutputBuilder {
+        /// Constructs a new builder with all fields as `None`.
+        pub fn new() -> Self {
+            Self {
+                outpoint: None,
+                value: None,
+                address: None,
+                height: None,
+            }
+        }
+
+        build_method!(outpoint, OutPoint);
+        build_method!(value, NonNegativeAmount);
+        build_method!(address, TransparentAddress);
+        build_method!(height, BlockHeight);
+
+        /// Builds the output after all fields have been set.
+        ///
+        /// # Panics
+        ///
+        /// `build` will panic if any field is `None`.
+        pub fn build(self) -> WalletTransparentOutput {
+            let txout = TxOut {
+                value: self.value.unwrap(),
+                script_pubkey: self.address.unwrap().script(),
+            };
+            WalletTransparentOutput::from_parts(
+                self.outpoint.unwrap(),
+                txout,
+                Some(self.height.unwrap()),
+            )
+            .expect("a mocked transparent output should always be valid")
+        }
+    }
+
+    impl Default for WalletTransparentOutputBuilder {
+        /// Constructs a builder preset with a mocked mined transparent output.
+        fn default() -> Self {
+            let mut builder = Self::new();
+            builder
+                .outpoint(OutPoint::new([0u8; 32], 0))
+                .value(NonNegativeAmount::const_from_u64(100_000))
+                .address(default_transparent_address())
+                .height(BlockHeight::from_u32(1));
+            builder
+        }
+    }
+}
+
 /// Orchard Note Mocker
 pub mod orchard_note {
 
@@ -272,8 +348,10 @@ pub mod proposal {
     use sapling_crypto::value::NoteValue;
 
     use sapling_crypto::Rseed;
+    use prost::Message as _;
     use zcash_client_backend::fees::TransactionBalance;
     use zcash_client_backend::proposal::{Proposal, ShieldedInputs, Step, StepOutput};
+    use zcash_client_backend::proto::proposal::{self, PROPOSAL_SER_V1};
     use zcash_client_backend::wallet::{ReceivedNote, WalletTransparentOutput};
     use zcash_client_backend::zip321::TransactionRequest;
     use zcash_client_backend::PoolType;
@@ -282,8 +360,10 @@ pub mod proposal {
         components::amount::NonNegativeAmount, fees::zip317::FeeRule,
     };
 
-    use zcash_client_backend::wallet::NoteId;
+    use zcash_client_backend::wallet::{Note, NoteId};
 
+    use super::orchard_note::OrchardCryptoNoteBuilder;
+    use super::transparent_output::WalletTransparentOutputBuilder;
     use super::{default_txid, default_zaddr};
 
     /// Provides a builder for constructing a mock [`zcash_client_backend::proposal::Proposal`].
@@ -337,6 +417,56 @@ pub mod proposal {
             )
             .unwrap()
         }
+
+        /// Builds the proposal and serializes it to its versioned protobuf wire format,
+        /// so tests can exercise the serialize -> persist -> deserialize path used by a
+        /// PCZT/hand-off flow.
+        pub fn build_serialized(self) -> Vec<u8> {
+            serialize_proposal(&self.build())
+        }
+    }
+
+    /// Encodes a [`Proposal`] to its `PROPOSAL_SER_V1`-tagged protobuf wire format.
+    pub fn serialize_proposal(proposal: &Proposal<FeeRule, NoteId>) -> Vec<u8> {
+        let proto = proposal::Proposal::from_standard_proposal(proposal);
+        let mut bytes = PROPOSAL_SER_V1.to_vec();
+        proto
+            .encode(&mut bytes)
+            .expect("encoding a valid in-memory Proposal cannot fail");
+        bytes
+    }
+
+    /// Decodes bytes previously produced by [`serialize_proposal`] back into a [`Proposal`],
+    /// asserting the `PROPOSAL_SER_V1` version tag and surfacing any failure as a
+    /// [`ProposalError`].
+    pub fn deserialize_proposal<P: zcash_primitives::consensus::Parameters>(
+        params: &P,
+        bytes: &[u8],
+    ) -> Result<Proposal<FeeRule, NoteId>, ProposalError> {
+        let tag_len = PROPOSAL_SER_V1.len();
+        if bytes.len() < tag_len || bytes[..tag_len] != PROPOSAL_SER_V1[..] {
+            return Err(ProposalError::WrongVersionTag);
+        }
+        let proto = proposal::Proposal::decode(&bytes[tag_len..])
+            .map_err(ProposalError::Decode)?;
+        proto
+            .try_into_standard_proposal(params)
+            .map_err(|e| ProposalError::Invalid(e.to_string()))
+    }
+
+    /// Errors produced while round-tripping a mocked [`Proposal`] through its protobuf
+    /// wire format.
+    #[derive(Debug, thiserror::Error)]
+    pub enum ProposalError {
+        /// The leading bytes did not match the expected [`PROPOSAL_SER_V1`] tag.
+        #[error("proposal bytes did not start with the expected PROPOSAL_SER_V1 tag")]
+        WrongVersionTag,
+        /// The protobuf bytes following the tag could not be decoded.
+        #[error("failed to decode proposal protobuf: {0}")]
+        Decode(#[from] prost::DecodeError),
+        /// The decoded protobuf did not represent a valid standard proposal.
+        #[error("decoded protobuf is not a valid proposal: {0}")]
+        Invalid(String),
     }
 
     impl Default for ProposalBuilder {
@@ -393,6 +523,26 @@ pub mod proposal {
         build_method!(balance, TransactionBalance);
         build_method!(is_shielding, bool);
 
+        /// Appends a shielded input to the step, creating the underlying
+        /// [`ShieldedInputs`] (anchored at height 1) if none has been set yet, or
+        /// appending to the existing set of notes otherwise. Because
+        /// [`zcash_client_backend::wallet::Note`] is an enum over Sapling and
+        /// Orchard notes, this lets a single step mix inputs from both pools.
+        pub fn add_shielded_input(&mut self, note: ReceivedNote<NoteId, Note>) -> &mut Self {
+            let notes = match self.shielded_inputs.take().flatten() {
+                Some(inputs) => {
+                    let mut notes = inputs.notes().clone();
+                    notes.push(note);
+                    notes
+                }
+                None => NonEmpty::singleton(note),
+            };
+            self.shielded_inputs(Some(ShieldedInputs::from_parts(
+                BlockHeight::from_u32(1),
+                notes,
+            )))
+        }
+
         /// Builds a step after all fields have been set.
         ///
         /// # Panics
@@ -415,6 +565,24 @@ pub mod proposal {
         }
     }
 
+    /// Builds a [`ReceivedNote`] wrapping a mocked Orchard note, analogous to the Sapling
+    /// `ReceivedNote` constructed inline in [`StepBuilder::default`].
+    pub fn mock_orchard_received_note(
+        txid: zcash_primitives::transaction::TxId,
+        index: u32,
+        position: Position,
+    ) -> ReceivedNote<NoteId, Note> {
+        let note = OrchardCryptoNoteBuilder::default().build();
+        ReceivedNote::from_parts(
+            NoteId::new(txid, zcash_client_backend::ShieldedProtocol::Orchard, index),
+            txid,
+            index as u16,
+            Note::Orchard(note),
+            zip32::Scope::External,
+            position,
+        )
+    }
+
     impl Default for StepBuilder {
         /// Constructs a new [`StepBuilder`] where all fields are preset to default values.
         fn default() -> Self {
@@ -452,4 +620,122 @@ pub mod proposal {
             builder
         }
     }
+
+    impl StepBuilder {
+        /// Constructs a [`StepBuilder`] preset for an auto-shielding step: one mocked
+        /// transparent input, no shielded inputs, and `is_shielding` set. This exercises
+        /// the transparent-input -> shielded-output construction flow that the
+        /// autoshield work introduced.
+        pub fn shielding_default() -> Self {
+            let mut builder = Self::new();
+            builder
+                .transaction_request(TransactionRequest::empty())
+                .payment_pools(BTreeMap::new())
+                .transparent_inputs(vec![WalletTransparentOutputBuilder::default().build()])
+                .shielded_inputs(None)
+                .prior_step_inputs(vec![])
+                .balance(
+                    TransactionBalance::new(vec![], NonNegativeAmount::const_from_u64(100_000))
+                        .unwrap(),
+                )
+                .is_shielding(true);
+            builder
+        }
+    }
+
+    /// Builders for mocking a [`zcash_client_backend::zip321::TransactionRequest`], so
+    /// tests can exercise steps that actually pay recipients, including memo-bearing
+    /// transfers.
+    pub mod zip321 {
+        use zcash_client_backend::zip321::{Payment, TransactionRequest};
+        use zcash_primitives::memo::MemoBytes;
+        use zcash_primitives::transaction::components::amount::NonNegativeAmount;
+
+        use super::super::build_method;
+
+        /// Provides a builder for constructing a mock
+        /// [`zcash_client_backend::zip321::Payment`].
+        pub struct PaymentBuilder {
+            recipient_address: Option<zcash_address::ZcashAddress>,
+            amount: Option<NonNegativeAmount>,
+            memo: Option<Option<MemoBytes>>,
+        }
+
+        impl PaymentBuilder {
+            /// Constructs a new [`PaymentBuilder`] with all fields as `None`.
+            pub fn new() -> Self {
+                PaymentBuilder {
+                    recipient_address: None,
+                    amount: None,
+                    memo: None,
+                }
+            }
+
+            build_method!(recipient_address, zcash_address::ZcashAddress);
+            build_method!(amount, NonNegativeAmount);
+            build_method!(memo, Option<MemoBytes>);
+
+            /// Builds the payment after all fields have been set.
+            ///
+            /// # Panics
+            ///
+            /// `build` will panic if `recipient_address` or `amount` are `None`.
+            pub fn build(self) -> Payment {
+                Payment::new(
+                    self.recipient_address.unwrap(),
+                    self.amount.unwrap(),
+                    self.memo.unwrap_or(None),
+                    None,
+                    None,
+                    vec![],
+                )
+                .expect("mocked payment fields should always be valid")
+            }
+        }
+
+        impl Default for PaymentBuilder {
+            fn default() -> Self {
+                PaymentBuilder::new()
+            }
+        }
+
+        /// Provides a builder for constructing a mock
+        /// [`zcash_client_backend::zip321::TransactionRequest`] from a list of
+        /// `(recipient_address, amount, memo)` payments.
+        #[derive(Default)]
+        pub struct TransactionRequestBuilder {
+            payments: Vec<Payment>,
+        }
+
+        impl TransactionRequestBuilder {
+            /// Constructs a new, empty [`TransactionRequestBuilder`].
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Appends a payment built from `(recipient_address, amount, memo)`.
+            pub fn add_payment(
+                &mut self,
+                recipient_address: zcash_address::ZcashAddress,
+                amount: NonNegativeAmount,
+                memo: Option<MemoBytes>,
+            ) -> &mut Self {
+                let mut builder = PaymentBuilder::new();
+                builder
+                    .recipient_address(recipient_address)
+                    .amount(amount)
+                    .memo(memo);
+                self.payments.push(builder.build());
+                self
+            }
+
+            /// Builds the [`TransactionRequest`] from all payments added so far.
+            ///
+            /// The resulting request's `total()` sums every payment's `amount`.
+            pub fn build(&self) -> TransactionRequest {
+                TransactionRequest::new(self.payments.clone())
+                    .expect("mocked payments should always form a valid request")
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -1,19 +1,22 @@
 //! An (incomplete) representation of what the Zingo instance "knows" about a transaction
-//! conspicuously absent is the set of transparent inputs to the transaction.
 //! by its`nature this evolves through, different states of completeness.
 
 use std::io::{self, Read, Write};
 
 use byteorder::{LittleEndian, ReadBytesExt as _, WriteBytesExt as _};
-use incrementalmerkletree::witness::IncrementalWitness;
-use orchard::tree::MerkleHashOrchard;
-use zcash_client_backend::PoolType;
-use zcash_primitives::{consensus::BlockHeight, transaction::TxId};
+use zcash_client_backend::{PoolType, ShieldedProtocol};
+use zcash_primitives::{
+    consensus::BlockHeight,
+    legacy::Script,
+    memo::Memo,
+    transaction::{components::OutPoint, TxId},
+};
 
 use crate::{
+    data::witness_trees::WitnessTrees,
     error::ZingoLibError,
     wallet::{
-        data::{OutgoingTxData, PoolNullifier, COMMITMENT_TREE_LEVELS},
+        data::{OutgoingTxData, PoolNullifier},
         keys::unified::WalletCapability,
         notes::{
             self, query::OutputQuery, OrchardNote, OutputId, OutputInterface as _, SaplingNote,
@@ -23,6 +26,76 @@ use crate::{
     },
 };
 
+const ZATOSHIS_PER_ZEC: u64 = 100_000_000;
+
+/// Converts a zatoshi amount to ZEC for fiat-value multiplication against a recorded
+/// ZEC/fiat price.
+fn zatoshis_to_zec(zatoshis: u64) -> f64 {
+    zatoshis as f64 / ZATOSHIS_PER_ZEC as f64
+}
+
+/// Classifies an [`crate::wallet::data::OutgoingTxData::to_address`] encoding by the pool it
+/// actually pays, for tagging an outgoing send's [`OutputId`] - transparent addresses can't
+/// carry a memo, so hardcoding every outgoing send as `PoolType::Transparent` would mislabel
+/// every real shielded memo match. A unified address is classified by its highest-preference
+/// receiver (Orchard, then Sapling), matching the pool this wallet itself prefers to pay to
+/// (see `LightWallet`'s change-pool preference); a legacy, single-receiver encoding is
+/// classified by its own address kind.
+fn pool_type_for_recipient(to_address: &str) -> PoolType {
+    use zcash_address::unified::{Address as UnifiedAddress, Container, Encoding, Receiver};
+
+    if let Ok((_, ua)) = UnifiedAddress::decode(to_address) {
+        if ua.items().iter().any(|r| matches!(r, Receiver::Orchard(_))) {
+            PoolType::Shielded(ShieldedProtocol::Orchard)
+        } else if ua.items().iter().any(|r| matches!(r, Receiver::Sapling(_))) {
+            PoolType::Shielded(ShieldedProtocol::Sapling)
+        } else {
+            PoolType::Transparent
+        }
+    } else if to_address.starts_with('t') {
+        PoolType::Transparent
+    } else {
+        PoolType::Shielded(ShieldedProtocol::Sapling)
+    }
+}
+
+/// A transparent UTXO that this wallet spent as an input to this transaction. Recording
+/// these (rather than only the pre-summed `total_transparent_value_spent`) lets the
+/// wallet reconstruct fees and detect double-spends of its own transparent funds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransparentInput {
+    /// The outpoint (previous txid and vout) of the UTXO this wallet spent.
+    pub prevout: OutPoint,
+    /// The value of the spent UTXO, in zatoshis.
+    pub value: u64,
+    /// The script of the address that had received the now-spent UTXO.
+    pub script_pubkey: Script,
+}
+
+impl TransparentInput {
+    /// TODO: Add Doc Comment Here!
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut hash = [0u8; 32];
+        reader.read_exact(&mut hash)?;
+        let n = reader.read_u32::<LittleEndian>()?;
+        let value = reader.read_u64::<LittleEndian>()?;
+        let script_pubkey = Script::read(&mut reader)?;
+        Ok(Self {
+            prevout: OutPoint::new(hash, n),
+            value,
+            script_pubkey,
+        })
+    }
+
+    /// TODO: Add Doc Comment Here!
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.prevout.hash())?;
+        writer.write_u32::<LittleEndian>(self.prevout.n())?;
+        writer.write_u64::<LittleEndian>(self.value)?;
+        self.script_pubkey.write(&mut writer)
+    }
+}
+
 ///  Everything (SOMETHING) about a transaction
 #[derive(Debug)]
 pub struct TransactionRecord {
@@ -51,6 +124,9 @@ pub struct TransactionRecord {
     /// List of all Utxos by this wallet received in this Tx. Some of these might be change notes
     pub transparent_outputs: Vec<TransparentOutput>,
 
+    /// List of all transparent UTXOs spent by this wallet as inputs to this Tx. Added in v24.
+    pub transparent_inputs: Vec<TransparentInput>,
+
     /// Total value of all the sapling nullifiers that were spent by this wallet in this Tx
     pub total_sapling_value_spent: u64,
 
@@ -84,6 +160,7 @@ impl TransactionRecord {
             sapling_notes: vec![],
             orchard_notes: vec![],
             transparent_outputs: vec![],
+            transparent_inputs: vec![],
             total_transparent_value_spent: 0,
             total_sapling_value_spent: 0,
             total_orchard_value_spent: 0,
@@ -113,9 +190,13 @@ impl TransactionRecord {
     pub fn query_for_ids(&self, include_notes: OutputQuery) -> Vec<OutputId> {
         let mut set = vec![];
         let spend_status_query = *include_notes.spend_status();
-        if *include_notes.transparent() {
+        // Transparent outputs never carry a memo, so a has-memo predicate excludes them
+        // entirely rather than matching or failing on an absent memo.
+        if *include_notes.transparent() && include_notes.has_memo().is_none() {
             for note in self.transparent_outputs.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                {
                     set.push(OutputId::from_parts(
                         self.txid,
                         PoolType::Transparent,
@@ -126,11 +207,14 @@ impl TransactionRecord {
         }
         if *include_notes.sapling() {
             for note in self.sapling_notes.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
                     if let Some(output_index) = note.output_index {
                         set.push(OutputId::from_parts(
                             self.txid,
-                            PoolType::Transparent,
+                            PoolType::Shielded(ShieldedProtocol::Sapling),
                             output_index,
                         ));
                     }
@@ -139,11 +223,14 @@ impl TransactionRecord {
         }
         if *include_notes.orchard() {
             for note in self.orchard_notes.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
                     if let Some(output_index) = note.output_index {
                         set.push(OutputId::from_parts(
                             self.txid,
-                            PoolType::Transparent,
+                            PoolType::Shielded(ShieldedProtocol::Orchard),
                             output_index,
                         ));
                     }
@@ -157,23 +244,31 @@ impl TransactionRecord {
     pub fn query_sum_value(&self, include_notes: OutputQuery) -> u64 {
         let mut sum = 0;
         let spend_status_query = *include_notes.spend_status();
-        if *include_notes.transparent() {
+        if *include_notes.transparent() && include_notes.has_memo().is_none() {
             for note in self.transparent_outputs.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                {
                     sum += note.value()
                 }
             }
         }
         if *include_notes.sapling() {
             for note in self.sapling_notes.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
                     sum += note.value()
                 }
             }
         }
         if *include_notes.orchard() {
             for note in self.orchard_notes.iter() {
-                if note.spend_status_query(spend_status_query) {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
                     sum += note.value()
                 }
             }
@@ -181,11 +276,122 @@ impl TransactionRecord {
         sum
     }
 
+    /// Companion to [`Self::query_sum_value`] that sums the fiat value of matched notes
+    /// rather than their zatoshi value, using each note's own `price` — the ZEC/fiat price
+    /// captured (by an injected price oracle, at scan time) for the block in which the
+    /// note was received, rather than this transaction's single `price` field. Notes
+    /// received before a price oracle was wired in, or deserialized from a wallet that
+    /// predates this field, carry `price: None` and are excluded from the sum; `None` is
+    /// returned only if every matched note lacks a price, so callers can tell "zero value"
+    /// from "unpriced" history.
+    pub fn query_sum_fiat_value(&self, include_notes: OutputQuery) -> Option<f64> {
+        let spend_status_query = *include_notes.spend_status();
+        let mut sum = 0.0;
+        let mut any_priced = false;
+        if *include_notes.sapling() {
+            for note in self.sapling_notes.iter() {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
+                    if let Some(price) = note.price {
+                        any_priced = true;
+                        sum += zatoshis_to_zec(note.value()) * price;
+                    }
+                }
+            }
+        }
+        if *include_notes.orchard() {
+            for note in self.orchard_notes.iter() {
+                if note.spend_status_query(spend_status_query)
+                    && include_notes.value_in_range(note.value())
+                    && include_notes.memo_matches(note.memo.as_ref())
+                {
+                    if let Some(price) = note.price {
+                        any_priced = true;
+                        sum += zatoshis_to_zec(note.value()) * price;
+                    }
+                }
+            }
+        }
+        any_priced.then_some(sum)
+    }
+
     /// TODO: Add Doc Comment Here!
     pub fn get_transparent_value_spent(&self) -> u64 {
         self.total_transparent_value_spent
     }
 
+    /// Returns the outpoints of every transparent UTXO this wallet spent as an input to
+    /// this transaction, analogous to [`Self::query_for_ids`] for received notes.
+    pub fn query_for_spent_transparent_outpoints(&self) -> Vec<OutPoint> {
+        self.transparent_inputs
+            .iter()
+            .map(|input| input.prevout.clone())
+            .collect()
+    }
+
+    /// Searches both received shielded notes and outgoing sends in this transaction for a
+    /// decoded [`Memo`] matching `predicate`, returning each match's [`OutputId`] paired
+    /// with the memo itself.
+    ///
+    /// Memos are decoded with the canonical ZIP-302 parse, so `predicate` sees the already
+    /// distinguished `Memo::Empty`/`Memo::Arbitrary`/`Memo::Text` variants rather than raw
+    /// bytes. Unlike the memo stipulations `OutputQuery` carries for `query_for_ids` and
+    /// `query_sum_value`, this takes an arbitrary predicate and also searches outgoing sends,
+    /// so it walks the note lists itself.
+    pub fn query_for_memos(
+        &self,
+        mut predicate: impl FnMut(&Memo) -> bool,
+    ) -> Vec<(OutputId, Memo)> {
+        let mut matches = vec![];
+        for note in self.sapling_notes.iter() {
+            if let Some(memo) = note.memo.as_ref() {
+                if predicate(memo) {
+                    if let Some(output_index) = note.output_index {
+                        matches.push((
+                            OutputId::from_parts(
+                                self.txid,
+                                PoolType::Shielded(ShieldedProtocol::Sapling),
+                                output_index,
+                            ),
+                            memo.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        for note in self.orchard_notes.iter() {
+            if let Some(memo) = note.memo.as_ref() {
+                if predicate(memo) {
+                    if let Some(output_index) = note.output_index {
+                        matches.push((
+                            OutputId::from_parts(
+                                self.txid,
+                                PoolType::Shielded(ShieldedProtocol::Orchard),
+                                output_index,
+                            ),
+                            memo.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        for (index, tx_data) in self.outgoing_tx_data.iter().enumerate() {
+            if predicate(&tx_data.memo) {
+                matches.push((
+                    OutputId::from_parts(
+                        self.txid,
+                        pool_type_for_recipient(&tx_data.to_address),
+                        index as u32,
+                    ),
+                    tx_data.memo.clone(),
+                ));
+            }
+        }
+        matches
+    }
+
     /// TODO: Add Doc Comment Here!
     pub fn get_transaction_fee(&self) -> Result<u64, ZingoLibError> {
         let outputted = self.value_outgoing() + self.total_change_returned();
@@ -205,6 +411,33 @@ impl TransactionRecord {
         }
     }
 
+    /// Computes the ZIP 317 conventional fee from the note/output counts already stored on
+    /// this record, without reverse-engineering it from spent-minus-returned value.
+    ///
+    /// Unlike [`Self::get_transaction_fee`], this only needs counts, so it's usable on
+    /// partially-decrypted or outgoing-only records where not every spent note is known.
+    /// Per ZIP 317, `fee = marginal_fee * max(grace_actions, logical_actions)`, where each
+    /// pool contributes `max(spends, outputs)` logical actions.
+    pub fn zip317_conventional_fee(&self) -> u64 {
+        const MARGINAL_FEE: u64 = 5_000;
+        const GRACE_ACTIONS: u64 = 2;
+
+        let transparent_actions =
+            std::cmp::max(self.transparent_inputs.len(), self.transparent_outputs.len());
+        let sapling_actions = std::cmp::max(
+            self.spent_sapling_nullifiers.len(),
+            self.sapling_notes.len(),
+        );
+        let orchard_actions = std::cmp::max(
+            self.spent_orchard_nullifiers.len(),
+            self.orchard_notes.len(),
+        );
+        let logical_actions =
+            (transparent_actions + sapling_actions + orchard_actions) as u64;
+
+        MARGINAL_FEE * std::cmp::max(GRACE_ACTIONS, logical_actions)
+    }
+
     /// For each Shielded note received in this transactions,
     /// pair it with a NoteRecordIdentifier identifying the note
     /// and return the list
@@ -297,6 +530,45 @@ impl TransactionRecord {
         ))
     }
 
+    /// Records `price` as this transaction's ZEC/fiat price, but only if one has not
+    /// already been captured at transaction-creation time. Intended for a caller that has
+    /// looked up a historical price for `self.datetime` from some price source and wants to
+    /// fill in a transaction that predates it (or was created offline), so
+    /// `fiat_value_received`/`fiat_value_spent`/`fiat_fee` stop returning `None` for it. No
+    /// such price-lookup caller exists yet - this is just the guarded setter it would call.
+    /// The backfilled price flows out through the existing versioned [`Self::write`] path
+    /// like any other price.
+    pub fn backfill_price(&mut self, price: f64) {
+        if self.price.is_none() {
+            self.price = Some(price);
+        }
+    }
+
+    /// The fiat value of [`Self::total_value_received`], using the ZEC price recorded (or
+    /// backfilled, see [`Self::backfill_price`]) for this transaction. `None` if no price
+    /// is known for this transaction.
+    pub fn fiat_value_received(&self) -> Option<f64> {
+        self.price
+            .map(|price| zatoshis_to_zec(self.total_value_received()) * price)
+    }
+
+    /// The fiat value of [`Self::net_spent`], using the ZEC price recorded (or backfilled)
+    /// for this transaction. `None` if no price is known for this transaction.
+    pub fn fiat_value_spent(&self) -> Option<f64> {
+        self.price
+            .map(|price| zatoshis_to_zec(self.net_spent()) * price)
+    }
+
+    /// The fiat value of [`Self::get_transaction_fee`], using the ZEC price recorded (or
+    /// backfilled) for this transaction. `Ok(None)` if no price is known for this
+    /// transaction.
+    pub fn fiat_fee(&self) -> Result<Option<f64>, ZingoLibError> {
+        let Some(price) = self.price else {
+            return Ok(None);
+        };
+        Ok(Some(zatoshis_to_zec(self.get_transaction_fee()?) * price))
+    }
+
     /// TODO: Add Doc Comment Here!
     pub fn total_value_spent(&self) -> u64 {
         self.value_spent_by_pool().iter().sum()
@@ -349,7 +621,10 @@ impl TransactionRecord {
                     txid,
                     index as u16,
                     note.note().clone(),
-                    zip32::Scope::External,
+                    // The note's own recorded scope (external vs. internal/change),
+                    // rather than assuming `External` and forcing callers to trial
+                    // re-derive against both IVKs to recover it.
+                    note.scope(),
                     pos,
                 )
             })
@@ -359,22 +634,16 @@ impl TransactionRecord {
 // read/write
 impl TransactionRecord {
     /// TODO: Add Doc Comment Here!
-    #[allow(clippy::type_complexity)]
+    ///
+    /// `trees`, when present, is the wallet's shared sharded commitment tree subsystem
+    /// (one `ShardTree` per shielded pool, each backed by a `MemoryShardStore`). Each note
+    /// only carries its witnessed leaf [`incrementalmerkletree::Position`]; the witness
+    /// itself is reconstructed on demand from the tree at read time, rather than being
+    /// stored per note as it was prior to v26. This keeps rewind on reorg an O(depth)
+    /// truncation of the shared tree instead of an O(notes) replay of individual witnesses.
     pub fn read<R: Read>(
         mut reader: R,
-        (wallet_capability, mut trees): (
-            &WalletCapability,
-            Option<&mut (
-                Vec<(
-                    IncrementalWitness<sapling_crypto::Node, COMMITMENT_TREE_LEVELS>,
-                    BlockHeight,
-                )>,
-                Vec<(
-                    IncrementalWitness<MerkleHashOrchard, COMMITMENT_TREE_LEVELS>,
-                    BlockHeight,
-                )>,
-            )>,
-        ),
+        (wallet_capability, mut trees): (&WalletCapability, Option<&mut WitnessTrees>),
     ) -> io::Result<Self> {
         let version = reader.read_u64::<LittleEndian>()?;
 
@@ -398,11 +667,23 @@ impl TransactionRecord {
         let transaction_id = TxId::from_bytes(transaction_id_bytes);
 
         let sapling_notes = zcash_encoding::Vector::read_collected_mut(&mut reader, |r| {
-            SaplingNote::read(r, (wallet_capability, trees.as_mut().map(|t| &mut t.0)))
+            SaplingNote::read(
+                r,
+                (
+                    wallet_capability,
+                    trees.as_deref_mut().map(|t| &mut t.witness_tree_sapling),
+                ),
+            )
         })?;
         let orchard_notes = if version > 22 {
             zcash_encoding::Vector::read_collected_mut(&mut reader, |r| {
-                OrchardNote::read(r, (wallet_capability, trees.as_mut().map(|t| &mut t.1)))
+                OrchardNote::read(
+                    r,
+                    (
+                        wallet_capability,
+                        trees.as_deref_mut().map(|t| &mut t.witness_tree_orchard),
+                    ),
+                )
             })?
         } else {
             vec![]
@@ -447,6 +728,12 @@ impl TransactionRecord {
                 Ok(orchard::note::Nullifier::from_bytes(&n).unwrap())
             })?
         };
+        let transparent_inputs = if version >= 24 {
+            zcash_encoding::Vector::read(&mut reader, |r| TransparentInput::read(r))?
+        } else {
+            vec![]
+        };
+
         let status = zingo_status::confirmation_status::ConfirmationStatus::from_blockheight_and_unconfirmed_bool(block, unconfirmed);
         Ok(Self {
             status,
@@ -455,6 +742,7 @@ impl TransactionRecord {
             sapling_notes,
             orchard_notes,
             transparent_outputs: utxos,
+            transparent_inputs,
             spent_sapling_nullifiers,
             spent_orchard_nullifiers,
             total_sapling_value_spent,
@@ -467,7 +755,14 @@ impl TransactionRecord {
 
     /// TODO: Add Doc Comment Here!
     pub fn serialized_version() -> u64 {
-        23
+        // v25 carries the per-note `zip32::Scope` (external vs. internal) alongside each
+        // `SaplingNote`/`OrchardNote`, recorded by `ShieldedNoteInterface::read`/`write`.
+        //
+        // v26 stops persisting a full `IncrementalWitness` per note: each `SaplingNote`/
+        // `OrchardNote` now records only its witnessed leaf `Position` in the wallet's
+        // shared `WitnessTrees`, with the witness itself reconstructed on demand from the
+        // corresponding `ShardTree` at read time.
+        26
     }
 
     /// TODO: Add Doc Comment Here!
@@ -507,6 +802,8 @@ impl TransactionRecord {
             w.write_all(&n.to_bytes())
         })?;
 
+        zcash_encoding::Vector::write(&mut writer, &self.transparent_inputs, |w, i| i.write(w))?;
+
         Ok(())
     }
 }
@@ -3,8 +3,16 @@
 //! implementations for TxMapAndMaybeTrees
 //! associated types for TxMapAndMaybeTrees that have no relevance elsewhere.
 
+use zcash_client_backend::{
+    fees::ChangeValue,
+    wallet::{Note, NoteId, ReceivedNote, WalletTransparentOutput},
+    ShieldedProtocol,
+};
+use zcash_primitives::transaction::components::{amount::NonNegativeAmount, OutPoint, TxOut};
+
 use crate::{
-    data::witness_trees::WitnessTrees, wallet::transaction_records_by_id::TransactionRecordsById,
+    data::witness_trees::WitnessTrees,
+    wallet::{notes::OutputInterface as _, transaction_records_by_id::TransactionRecordsById},
 };
 
 /// HashMap of all transactions in a wallet, keyed by txid.
@@ -13,23 +21,47 @@ use crate::{
 pub struct TxMapAndMaybeTrees {
     pub transaction_records_by_id: TransactionRecordsById,
     witness_trees: Option<WitnessTrees>,
+    /// The pluggable persistence backend selected at construction. `transaction_records_by_id`
+    /// above remains the in-memory source of truth that `get`/`recording`/`read_write`
+    /// operate on; every [`Self::transaction`] commit additionally serializes each record
+    /// into this store, keyed by txid, via [`Self::persist_records`], so the records
+    /// committed so far survive a crash even though the in-memory map does not shrink. See
+    /// [`store`] for the trait and the backends that implement it.
+    store: Box<dyn store::WalletStore>,
 }
 
 pub mod get;
 pub mod read_write;
 pub mod recording;
+pub mod store;
 
 impl TxMapAndMaybeTrees {
     pub(crate) fn new_with_witness_trees() -> TxMapAndMaybeTrees {
+        Self::new_with_witness_trees_and_store(Box::new(store::InMemoryStore::new()))
+    }
+    pub(crate) fn new_treeless() -> TxMapAndMaybeTrees {
+        Self::new_treeless_with_store(Box::new(store::InMemoryStore::new()))
+    }
+    /// As [`Self::new_with_witness_trees`], but persisting through `store` instead of the
+    /// default [`store::InMemoryStore`] - for instance a [`store::SledStore`]. Every record
+    /// is still held in `transaction_records_by_id` as well, so the store adds durability
+    /// but does not reduce how many records are kept in RAM.
+    pub(crate) fn new_with_witness_trees_and_store(
+        store: Box<dyn store::WalletStore>,
+    ) -> TxMapAndMaybeTrees {
         Self {
             transaction_records_by_id: TransactionRecordsById::new(),
             witness_trees: Some(WitnessTrees::default()),
+            store,
         }
     }
-    pub(crate) fn new_treeless() -> TxMapAndMaybeTrees {
+    /// As [`Self::new_treeless`], but persisting through `store` instead of the default
+    /// [`store::InMemoryStore`].
+    pub(crate) fn new_treeless_with_store(store: Box<dyn store::WalletStore>) -> TxMapAndMaybeTrees {
         Self {
             transaction_records_by_id: TransactionRecordsById::new(),
             witness_trees: None,
+            store,
         }
     }
     pub fn witness_trees(&self) -> Option<&WitnessTrees> {
@@ -38,9 +70,450 @@ impl TxMapAndMaybeTrees {
     pub(crate) fn witness_trees_mut(&mut self) -> Option<&mut WitnessTrees> {
         self.witness_trees.as_mut()
     }
+    /// The pluggable persistence backend this wallet was constructed with.
+    pub fn store(&self) -> &dyn store::WalletStore {
+        self.store.as_ref()
+    }
+    pub(crate) fn store_mut(&mut self) -> &mut dyn store::WalletStore {
+        self.store.as_mut()
+    }
     pub fn clear(&mut self) {
-        self.transaction_records_by_id.clear();
-        self.witness_trees.as_mut().map(WitnessTrees::clear);
+        let _: Result<(), std::convert::Infallible> = self.transaction(|txn| {
+            txn.record(|records| records.clear());
+            txn.witness_trees(WitnessTrees::clear);
+            Ok(())
+        });
+        self.store.clear();
+    }
+
+    /// Runs `body` against a [`Txn`] handle that only *queues* record and witness-tree
+    /// mutations, then applies the whole queued batch as a single atomic unit: if `body`
+    /// returns `Ok`, every queued mutation is applied, in the order queued, and the
+    /// persistence backend is flushed; if it returns `Err` - or panics, since nothing is
+    /// applied until after `body` returns - `transaction_records_by_id` and the witness
+    /// trees are left exactly as they were, with no partially-applied mutations visible.
+    ///
+    /// [`Self::clear`] is built on this directly; block-scan ingestion and reorg rewinds are
+    /// the motivating future callers, for the same reason - an interruption partway through
+    /// must not leave records updated but witness trees stale, or vice versa - but neither is
+    /// wired up to call through this yet.
+    pub fn transaction<E>(
+        &mut self,
+        body: impl FnOnce(&mut Txn) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut txn = Txn {
+            record_mutations: Vec::new(),
+            witness_mutations: Vec::new(),
+        };
+        body(&mut txn)?;
+        for mutation in txn.record_mutations {
+            mutation(&mut self.transaction_records_by_id);
+        }
+        if let Some(witness_trees) = self.witness_trees.as_mut() {
+            for mutation in txn.witness_mutations {
+                mutation(witness_trees);
+            }
+        }
+        self.persist_records();
+        self.store.flush();
+        Ok(())
+    }
+
+    /// Serializes every record currently in `transaction_records_by_id` and writes it into
+    /// `store`, keyed by its txid bytes, overwriting whatever was stored for that txid
+    /// before. Called at the end of every committed [`Self::transaction`] batch so the store
+    /// actually reflects what's in memory, rather than sitting unused.
+    fn persist_records(&mut self) {
+        for record in self.transaction_records_by_id.values() {
+            let mut bytes = Vec::new();
+            if record.write(&mut bytes).is_ok() {
+                self.store.insert(record.txid.as_ref().to_vec(), bytes);
+            }
+        }
+    }
+
+    /// Looks up the raw serialized bytes [`Self::persist_records`] wrote for `txid`, if any
+    /// committed `transaction` batch has persisted it. Exposed mainly so the store can be
+    /// inspected/tested independently of the in-memory `transaction_records_by_id`.
+    pub fn persisted_record_bytes(&self, txid: &zcash_primitives::transaction::TxId) -> Option<Vec<u8>> {
+        self.store.get(txid.as_ref())
+    }
+}
+
+/// A handle passed to the closure given to [`TxMapAndMaybeTrees::transaction`]. Queues
+/// record and witness-tree mutations without applying them; the transaction applies every
+/// queued mutation only once the closure returns `Ok`.
+#[derive(Default)]
+pub struct Txn {
+    record_mutations: Vec<Box<dyn FnOnce(&mut TransactionRecordsById)>>,
+    witness_mutations: Vec<Box<dyn FnOnce(&mut WitnessTrees)>>,
+}
+
+impl Txn {
+    /// Queues a mutation of the wallet's transaction records, applied only once the
+    /// enclosing transaction commits.
+    pub fn record(&mut self, mutation: impl FnOnce(&mut TransactionRecordsById) + 'static) {
+        self.record_mutations.push(Box::new(mutation));
+    }
+
+    /// Queues a mutation of the wallet's witness trees, applied only once the enclosing
+    /// transaction commits. Silently dropped if the wallet has no witness trees (a
+    /// viewkey-watch wallet constructed via [`TxMapAndMaybeTrees::new_treeless`]).
+    pub fn witness_trees(&mut self, mutation: impl FnOnce(&mut WitnessTrees) + 'static) {
+        self.witness_mutations.push(Box::new(mutation));
+    }
+}
+
+/// The chosen inputs and per-pool change for a cross-pool spend sized to cover a target
+/// value plus its own ZIP 317 fee, as produced by
+/// [`TxMapAndMaybeTrees::select_spendable_value`].
+///
+/// This is the selection half of a proposal: turning it into a full
+/// `zcash_client_backend::proposal::Proposal` is a matter of handing `orchard_inputs` and
+/// `sapling_inputs` to a `Step`'s shielded inputs, `transparent_inputs` to its transparent
+/// inputs, and `change` plus `fee` to a `TransactionBalance`, alongside whatever
+/// `TransactionRequest` the caller is funding.
+#[derive(Debug, Clone)]
+pub struct SelectedSpend {
+    /// Selected Orchard notes, in the order they were chosen.
+    pub orchard_inputs: Vec<ReceivedNote<NoteId, Note>>,
+    /// Selected Sapling notes, in the order they were chosen.
+    pub sapling_inputs: Vec<ReceivedNote<NoteId, Note>>,
+    /// Selected transparent UTXOs, chosen only once the shielded pools can't alone cover
+    /// the target, in the order they were chosen.
+    pub transparent_inputs: Vec<WalletTransparentOutput>,
+    /// Per-pool change returned to the change address, for whatever dust is left over once
+    /// the target and fee are covered.
+    pub change: Vec<ChangeValue>,
+    /// The ZIP 317 conventional fee required by the final input/output set, including the
+    /// change outputs themselves.
+    pub fee: NonNegativeAmount,
+}
+
+/// A payment fragmented into multiple, smaller outputs, paired with the inputs and change
+/// selected to fund all of them, as produced by
+/// [`TxMapAndMaybeTrees::select_spendable_value_split`].
+#[derive(Debug, Clone)]
+pub struct SplitSpend {
+    /// The amount of each fragment the payment was split into, none larger than the
+    /// `max_amount_per_note` ceiling that was passed in, in the order they should be paid.
+    pub outputs: Vec<NonNegativeAmount>,
+    /// The inputs and change selected to fund every one of `outputs`, plus their fee.
+    pub selection: SelectedSpend,
+}
+
+/// Errors produced while selecting spendable value across pools.
+#[derive(Debug, PartialEq)]
+pub enum SelectionError {
+    /// The wallet's unspent notes and UTXOs, across every pool, do not sum to at least
+    /// `target` plus the fee the resulting input set would require.
+    InsufficientFunds {
+        /// The value that was being selected for, not including fee.
+        target: u64,
+        /// The total value actually available for selection.
+        available: u64,
+    },
+}
+
+const MARGINAL_FEE: u64 = 5_000;
+const GRACE_ACTIONS: u64 = 2;
+
+/// The ZIP 317 conventional fee for `logical_actions` logical actions. Mirrors
+/// `TransactionRecord::zip317_conventional_fee`, but operates on an action count being
+/// built up incrementally during selection rather than one read off a finished record.
+fn zip317_fee_for_actions(logical_actions: u64) -> NonNegativeAmount {
+    NonNegativeAmount::const_from_u64(MARGINAL_FEE * std::cmp::max(GRACE_ACTIONS, logical_actions))
+}
+
+/// The ZIP 317 logical-action count for a cross-pool selection: the sum, over each pool, of
+/// `max(spends in that pool, outputs in that pool)`. Mirrors
+/// `TransactionRecord::zip317_conventional_fee`, which derives the same sum from a finished
+/// record's note/nullifier counts instead of counts being built up incrementally here.
+///
+/// Summing the per-pool maxima (rather than taking one max of the totals, or summing the
+/// totals outright) matters whenever spends and outputs don't land in the same pool: e.g.
+/// sapling and transparent spends funding an orchard output are three logical actions
+/// (`max(spends, 0)` in each spent pool, plus `max(0, outputs)` in the output's pool), not
+/// one combined max across every pool.
+fn cross_pool_logical_actions(
+    orchard_spends: u64,
+    orchard_outputs: u64,
+    sapling_spends: u64,
+    sapling_outputs: u64,
+    transparent_spends: u64,
+    transparent_outputs: u64,
+) -> u64 {
+    std::cmp::max(orchard_spends, orchard_outputs)
+        + std::cmp::max(sapling_spends, sapling_outputs)
+        + std::cmp::max(transparent_spends, transparent_outputs)
+}
+
+/// Splits `total` into a series of output amounts, none larger than
+/// `max_amount_per_note`, by filling notes up to the ceiling and putting whatever is left
+/// into a final, smaller note. For instance 625 ZEC capped at 100 ZEC/note yields seven
+/// outputs: six of 100 ZEC and one of 25 ZEC.
+///
+/// Returns a single output equal to `total` when `total <= max_amount_per_note`, and an
+/// empty vec when `total` is zero.
+pub fn split_payment_amounts(
+    total: NonNegativeAmount,
+    max_amount_per_note: NonNegativeAmount,
+) -> Vec<NonNegativeAmount> {
+    let mut remaining = total.into_u64();
+    let cap = max_amount_per_note.into_u64();
+    let mut outputs = Vec::new();
+    while remaining > 0 {
+        let chunk = std::cmp::min(remaining, cap);
+        outputs.push(NonNegativeAmount::const_from_u64(chunk));
+        remaining -= chunk;
+    }
+    outputs
+}
+
+impl TxMapAndMaybeTrees {
+    /// Greedily selects unspent notes and UTXOs across every pool in this wallet to cover
+    /// `target` plus the ZIP 317 fee the resulting input set requires, preferring Orchard,
+    /// then Sapling, then transparent inputs last, so a send touches as few pools - and
+    /// therefore leaks as little cross-pool linkage - as possible.
+    ///
+    /// The logical-action count (and so the fee) is recomputed after every input is added,
+    /// and once more for the change output when value is left over. The change output is
+    /// only added if the selection also covers the fee it raises; otherwise the leftover is
+    /// not returned as change. Either way the returned [`SelectedSpend::fee`] matches the
+    /// actual final input/output set rather than an estimate made before change was known
+    /// to be necessary.
+    ///
+    /// `output_pool` is the pool the recipient's output(s) land in - `None` for a
+    /// transparent recipient - so the fee can credit each pool's own `max(spends, outputs)`
+    /// rather than conflating outputs with whichever pool happened to be spent from.
+    pub fn select_spendable_value(
+        &self,
+        target: NonNegativeAmount,
+        output_pool: Option<ShieldedProtocol>,
+    ) -> Result<SelectedSpend, SelectionError> {
+        self.select_spendable_value_for_outputs(target, 1, output_pool)
+    }
+
+    /// Splits `target` into a series of outputs no larger than `max_amount_per_note` (see
+    /// [`split_payment_amounts`]) and selects enough unspent notes and UTXOs, via the same
+    /// cross-pool preference as [`Self::select_spendable_value`], to fund every one of
+    /// those outputs plus the ZIP 317 fee they collectively require - each extra output
+    /// beyond the first is its own logical action, and so can itself raise the fee.
+    ///
+    /// Splitting large sends this way avoids a single oversized note standing out in the
+    /// shielded pool, and leaves later spends more, smaller notes to choose from. Every
+    /// fragment pays the same recipient, so they all share one `output_pool` (see
+    /// [`Self::select_spendable_value`]).
+    pub fn select_spendable_value_split(
+        &self,
+        target: NonNegativeAmount,
+        max_amount_per_note: NonNegativeAmount,
+        output_pool: Option<ShieldedProtocol>,
+    ) -> Result<SplitSpend, SelectionError> {
+        let outputs = split_payment_amounts(target, max_amount_per_note);
+        let selection = self.select_spendable_value_for_outputs(
+            target,
+            outputs.len() as u64,
+            output_pool,
+        )?;
+        Ok(SplitSpend { outputs, selection })
+    }
+
+    /// Shared selection core behind [`Self::select_spendable_value`] and
+    /// [`Self::select_spendable_value_split`]. `output_actions` is the number of
+    /// recipient outputs the caller intends to fund, all landing in `output_pool` (`None`
+    /// meaning transparent), which - like the selected input count - contributes to the
+    /// ZIP 317 logical-action count and so the fee.
+    fn select_spendable_value_for_outputs(
+        &self,
+        target: NonNegativeAmount,
+        output_actions: u64,
+        output_pool: Option<ShieldedProtocol>,
+    ) -> Result<SelectedSpend, SelectionError> {
+        let orchard_output_actions = if output_pool == Some(ShieldedProtocol::Orchard) {
+            output_actions
+        } else {
+            0
+        };
+        let sapling_output_actions = if output_pool == Some(ShieldedProtocol::Sapling) {
+            output_actions
+        } else {
+            0
+        };
+        let transparent_output_actions = if output_pool.is_none() { output_actions } else { 0 };
+        let mut orchard_inputs = Vec::new();
+        let mut sapling_inputs = Vec::new();
+        let mut transparent_inputs = Vec::new();
+        let mut selected_value = 0u64;
+
+        // One logical action per selected input, plus one more if a change output ends up
+        // being needed - accounted for only once selection has converged below.
+        let mut fee = zip317_fee_for_actions(cross_pool_logical_actions(
+            0,
+            orchard_output_actions,
+            0,
+            sapling_output_actions,
+            0,
+            transparent_output_actions,
+        ));
+
+        'pools: for record in self.transaction_records_by_id.values() {
+            for (note, id) in
+                record.select_unspent_shnotes_and_ids::<orchard::note_encryption::OrchardDomain>()
+            {
+                if selected_value >= target.into_u64() + fee.into_u64() {
+                    break 'pools;
+                }
+                let Some(received) =
+                    record.get_received_note::<orchard::note_encryption::OrchardDomain>(id.index)
+                else {
+                    continue;
+                };
+                selected_value += u64::from(note.value().inner());
+                orchard_inputs.push(ReceivedNote::from_parts(
+                    NoteId::new(received.txid(), ShieldedProtocol::Orchard, id.index as u16),
+                    received.txid(),
+                    id.index as u16,
+                    Note::Orchard(note),
+                    *received.spending_key_scope(),
+                    received.witnessed_position(),
+                ));
+                fee = zip317_fee_for_actions(cross_pool_logical_actions(
+                    orchard_inputs.len() as u64,
+                    orchard_output_actions,
+                    sapling_inputs.len() as u64,
+                    sapling_output_actions,
+                    transparent_inputs.len() as u64,
+                    transparent_output_actions,
+                ));
+            }
+        }
+
+        'pools: for record in self.transaction_records_by_id.values() {
+            if selected_value >= target.into_u64() + fee.into_u64() {
+                break 'pools;
+            }
+            for (note, id) in
+                record.select_unspent_shnotes_and_ids::<sapling_crypto::note_encryption::SaplingDomain>(
+                )
+            {
+                if selected_value >= target.into_u64() + fee.into_u64() {
+                    break 'pools;
+                }
+                let Some(received) =
+                    record.get_received_note::<sapling_crypto::note_encryption::SaplingDomain>(
+                        id.index,
+                    )
+                else {
+                    continue;
+                };
+                selected_value += note.value().inner();
+                sapling_inputs.push(ReceivedNote::from_parts(
+                    NoteId::new(received.txid(), ShieldedProtocol::Sapling, id.index as u16),
+                    received.txid(),
+                    id.index as u16,
+                    Note::Sapling(note),
+                    *received.spending_key_scope(),
+                    received.witnessed_position(),
+                ));
+                fee = zip317_fee_for_actions(cross_pool_logical_actions(
+                    orchard_inputs.len() as u64,
+                    orchard_output_actions,
+                    sapling_inputs.len() as u64,
+                    sapling_output_actions,
+                    transparent_inputs.len() as u64,
+                    transparent_output_actions,
+                ));
+            }
+        }
+
+        // Transparent inputs are the last resort: spending one forgoes the privacy benefit
+        // that preferring the shielded pools above is meant to preserve. The wallet's
+        // transparent UTXO set is tracked per `TransactionRecord::transparent_outputs`
+        // rather than through `select_unspent_shnotes_and_ids`, which is shielded-only.
+        'pools: for record in self.transaction_records_by_id.values() {
+            if selected_value >= target.into_u64() + fee.into_u64() {
+                break 'pools;
+            }
+            for output in record.transparent_outputs.iter() {
+                if selected_value >= target.into_u64() + fee.into_u64() {
+                    break 'pools;
+                }
+                if output.is_spent_or_pending_spent() {
+                    continue;
+                }
+                let Some(value) = NonNegativeAmount::from_u64(output.value).ok() else {
+                    continue;
+                };
+                let hash: [u8; 32] = output
+                    .txid
+                    .as_ref()
+                    .try_into()
+                    .expect("a TxId is always 32 bytes");
+                let Some(wallet_output) = WalletTransparentOutput::from_parts(
+                    OutPoint::new(hash, output.output_index as u32),
+                    TxOut {
+                        value,
+                        script_pubkey: output.script.clone(),
+                    },
+                    Some(record.status.get_height()),
+                ) else {
+                    continue;
+                };
+                selected_value += output.value;
+                transparent_inputs.push(wallet_output);
+                fee = zip317_fee_for_actions(cross_pool_logical_actions(
+                    orchard_inputs.len() as u64,
+                    orchard_output_actions,
+                    sapling_inputs.len() as u64,
+                    sapling_output_actions,
+                    transparent_inputs.len() as u64,
+                    transparent_output_actions,
+                ));
+            }
+        }
+
+        if selected_value < target.into_u64() + fee.into_u64() {
+            return Err(SelectionError::InsufficientFunds {
+                target: target.into_u64(),
+                available: selected_value,
+            });
+        }
+
+        let leftover = selected_value - target.into_u64() - fee.into_u64();
+        let mut change = Vec::new();
+        if leftover > 0 {
+            // Recompute the fee once more with the change output's own logical action
+            // included, since a change output can itself push the action count (and so
+            // the fee) up by one. That fee is kept only if the change output is added.
+            let prefer_orchard = !orchard_inputs.is_empty();
+            let fee_with_change = zip317_fee_for_actions(cross_pool_logical_actions(
+                orchard_inputs.len() as u64,
+                orchard_output_actions + if prefer_orchard { 1 } else { 0 },
+                sapling_inputs.len() as u64,
+                sapling_output_actions + if prefer_orchard { 0 } else { 1 },
+                transparent_inputs.len() as u64,
+                transparent_output_actions,
+            ));
+            if selected_value >= target.into_u64() + fee_with_change.into_u64() {
+                fee = fee_with_change;
+                let change_value = selected_value - target.into_u64() - fee.into_u64();
+                let change_amount = NonNegativeAmount::from_u64(change_value).unwrap();
+                change.push(if prefer_orchard {
+                    ChangeValue::orchard(change_amount, None)
+                } else {
+                    ChangeValue::sapling(change_amount, None)
+                });
+            }
+        }
+
+        Ok(SelectedSpend {
+            orchard_inputs,
+            sapling_inputs,
+            transparent_inputs,
+            change,
+            fee,
+        })
     }
 }
 
@@ -71,3 +544,138 @@ pub mod error {
 }
 
 pub mod trait_walletread;
+
+pub mod store {
+    //! A pluggable storage backend for transaction-record persistence. Records are written
+    //! to the store in addition to the in-memory `TransactionRecordsById`, not instead of
+    //! it, so every [`TransactionRecord`] stays in RAM whichever backend is used.
+    //! [`TxMapAndMaybeTrees::new_with_witness_trees`] and
+    //! [`TxMapAndMaybeTrees::new_treeless`] default to [`InMemoryStore`]; the
+    //! `_with_store` constructors additionally accept a [`SledStore`] or any other
+    //! [`WalletStore`] implementation.
+    //!
+    //! [`TransactionRecord`]: crate::wallet::transaction_record::TransactionRecord
+
+    use std::collections::BTreeMap;
+
+    /// A minimal key/value database interface that a wallet's transaction-record storage
+    /// can be backed by. Keys and values are opaque bytes - typically a serialized
+    /// `TxId`/pool index as the key and a `TransactionRecord::write` payload as the value -
+    /// so anything from a plain in-memory map up to an embedded database can implement it.
+    pub trait WalletStore: Send + Sync {
+        /// Looks up the value stored for `key`, if any.
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+        /// Inserts the value stored for `key`, overwriting any value already there.
+        fn insert(&mut self, key: Vec<u8>, value: Vec<u8>);
+        /// Removes and returns the value stored for `key`, if any.
+        fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>>;
+        /// Iterates every key/value pair currently stored, in key order.
+        fn iter(&self) -> Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_>;
+        /// Removes every key/value pair.
+        fn clear(&mut self);
+        /// Flushes any buffered writes to durable storage. A no-op for a backend (like
+        /// [`InMemoryStore`]) with no separate write buffer to flush.
+        fn flush(&mut self) {}
+    }
+
+    /// The default, fully in-memory [`WalletStore`]. A wallet using this backend still
+    /// holds every transaction record in RAM, exactly as `TransactionRecordsById` already
+    /// does on its own; it exists so callers have a working backend without reaching for
+    /// a real database.
+    #[derive(Debug, Default)]
+    pub struct InMemoryStore {
+        entries: BTreeMap<Vec<u8>, Vec<u8>>,
+    }
+
+    impl InMemoryStore {
+        /// Constructs an empty in-memory store.
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    impl WalletStore for InMemoryStore {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.entries.get(key).cloned()
+        }
+        fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+            self.entries.insert(key, value);
+        }
+        fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+            self.entries.remove(key)
+        }
+        fn iter(&self) -> Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_> {
+            Box::new(self.entries.iter().map(|(k, v)| (k.clone(), v.clone())))
+        }
+        fn clear(&mut self) {
+            self.entries.clear();
+        }
+    }
+
+    /// A [`WalletStore`] backed by a [`sled`] embedded database, for crash durability across
+    /// wallet restarts.
+    pub struct SledStore {
+        db: sled::Db,
+    }
+
+    impl SledStore {
+        /// Opens (creating if necessary) a sled database at `path`.
+        pub fn open(path: impl AsRef<std::path::Path>) -> sled::Result<Self> {
+            Ok(Self {
+                db: sled::open(path)?,
+            })
+        }
+    }
+
+    impl WalletStore for SledStore {
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.db.get(key).ok().flatten().map(|ivec| ivec.to_vec())
+        }
+        fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+            let _ = self.db.insert(key, value);
+        }
+        fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+            self.db.remove(key).ok().flatten().map(|ivec| ivec.to_vec())
+        }
+        fn iter(&self) -> Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + '_> {
+            Box::new(
+                self.db
+                    .iter()
+                    .filter_map(|entry| entry.ok().map(|(k, v)| (k.to_vec(), v.to_vec()))),
+            )
+        }
+        fn clear(&mut self) {
+            let _ = self.db.clear();
+        }
+        fn flush(&mut self) {
+            let _ = self.db.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cross_pool_logical_actions;
+
+    #[test]
+    fn single_pool_spends_and_outputs_match_the_conventional_formula() {
+        // 3 sapling spends funding 2 sapling outputs: max(3, 2) = 3 actions, same as the
+        // flat max(output_actions, total_inputs) the old code computed for a single pool.
+        assert_eq!(cross_pool_logical_actions(0, 0, 3, 2, 0, 0), 3);
+    }
+
+    #[test]
+    fn cross_pool_spends_sum_per_pool_maxima_instead_of_maxing_the_totals() {
+        // 2 sapling inputs and 1 transparent input funding 1 orchard output: each pool
+        // contributes its own max(spends, outputs) - sapling max(2, 0) = 2, transparent
+        // max(1, 0) = 1, orchard max(0, 1) = 1 - for 4 logical actions total. The old
+        // `max(output_actions, total_inputs)` formula conflated these into max(1, 3) = 3,
+        // undercounting by one action's worth of fee.
+        assert_eq!(cross_pool_logical_actions(0, 1, 2, 0, 1, 0), 4);
+    }
+
+    #[test]
+    fn an_empty_selection_still_counts_its_outputs() {
+        assert_eq!(cross_pool_logical_actions(0, 1, 0, 0, 0, 0), 1);
+    }
+}